@@ -0,0 +1,232 @@
+use crate::big_int::BigInt;
+use std::{convert::TryFrom, error::Error, fmt};
+
+// How wide an answer may grow. Arithmetic starts in 64 bits and is promoted
+// to a wider representation when a result no longer fits, up to this limit.
+#[derive(Clone, Copy, Debug, Eq, Ord, PartialEq, PartialOrd)]
+pub enum Precision {
+    // Anything a signed or an unsigned 64-bit integer can hold, so a product
+    // of counts is allowed whenever it fits in a u64.
+    Bits64,
+    Bits128,
+    Unbounded,
+}
+
+// A puzzle answer, held in the narrowest representation it fits in, so two
+// answers are equal exactly when their values are.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum Answer {
+    Small(i64),
+    Wide(i128),
+    Big(BigInt),
+}
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct OverflowError {
+    pub precision: Precision,
+}
+
+impl fmt::Display for OverflowError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self.precision {
+            Precision::Bits64 => write!(
+                f,
+                "answer does not fit in a signed or unsigned 64-bit integer"
+            ),
+            Precision::Bits128 => write!(f, "answer does not fit in 128 bits"),
+            // A big integer only runs out when memory does.
+            Precision::Unbounded => write!(f, "answer is too large to represent"),
+        }
+    }
+}
+
+impl Error for OverflowError {}
+
+impl Answer {
+    fn from_wide(value: i128) -> Answer {
+        match i64::try_from(value) {
+            Ok(value) => Answer::Small(value),
+            Err(_) => Answer::Wide(value),
+        }
+    }
+
+    fn from_big(value: BigInt) -> Answer {
+        match i128::try_from(&value) {
+            Ok(value) => Answer::from_wide(value),
+            Err(_) => Answer::Big(value),
+        }
+    }
+
+    fn to_big(&self) -> BigInt {
+        match self {
+            Answer::Small(value) => BigInt::from(*value),
+            Answer::Wide(value) => BigInt::from(*value),
+            Answer::Big(value) => value.clone(),
+        }
+    }
+
+    fn precision(&self) -> Precision {
+        match self {
+            Answer::Small(_) => Precision::Bits64,
+            Answer::Wide(_) => Precision::Bits128,
+            Answer::Big(_) => Precision::Unbounded,
+        }
+    }
+
+    fn fits(&self, limit: Precision) -> bool {
+        match (self, limit) {
+            (Answer::Wide(value), Precision::Bits64) => u64::try_from(*value).is_ok(),
+            _ => self.precision() <= limit,
+        }
+    }
+
+    // Multiplies, promoting the answer if the product does not fit and the
+    // limit allows it.
+    pub fn checked_mul(&self, factor: i128, limit: Precision) -> Result<Answer, OverflowError> {
+        let wide = match self {
+            Answer::Small(value) => (*value as i128).checked_mul(factor),
+            Answer::Wide(value) => value.checked_mul(factor),
+            Answer::Big(_) => None,
+        };
+        let product = match wide {
+            Some(product) => Answer::from_wide(product),
+            None => Answer::from_big(&self.to_big() * &BigInt::from(factor)),
+        };
+        if product.fits(limit) {
+            Ok(product)
+        } else {
+            Err(OverflowError { precision: limit })
+        }
+    }
+
+    pub fn product<I>(factors: I, limit: Precision) -> Result<Answer, OverflowError>
+    where
+        I: IntoIterator,
+        I::Item: Into<i128>,
+    {
+        factors
+            .into_iter()
+            .try_fold(Answer::Small(1), |answer, factor| {
+                answer.checked_mul(factor.into(), limit)
+            })
+    }
+}
+
+impl From<i64> for Answer {
+    fn from(value: i64) -> Self {
+        Answer::Small(value)
+    }
+}
+
+impl fmt::Display for Answer {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Answer::Small(value) => value.fmt(f),
+            Answer::Wide(value) => value.fmt(f),
+            Answer::Big(value) => value.fmt(f),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_product_within_64_bits() {
+        assert_eq!(
+            Answer::product([1721u32, 299], Precision::Bits64),
+            Ok(Answer::Small(514579))
+        );
+        assert_eq!(
+            Answer::product(Vec::<u64>::new(), Precision::Bits64),
+            Ok(Answer::Small(1))
+        );
+        assert_eq!(
+            Answer::product([-3i64, 7], Precision::Bits64),
+            Ok(Answer::Small(-21))
+        );
+    }
+
+    #[test]
+    fn test_product_up_to_unsigned_64_bits() {
+        assert_eq!(
+            Answer::product([u64::MAX], Precision::Bits64),
+            Ok(Answer::Wide(u64::MAX as i128))
+        );
+        assert_eq!(
+            Answer::product([1u64 << 32, 1 << 31], Precision::Bits64).map(|a| a.to_string()),
+            Ok("9223372036854775808".to_string())
+        );
+        assert_eq!(
+            Answer::product([i64::MIN], Precision::Bits64),
+            Ok(Answer::Small(i64::MIN))
+        );
+    }
+
+    #[test]
+    fn test_product_overflow_is_an_error() {
+        let factors = [u32::MAX as u64, u32::MAX as u64, 4];
+        assert_eq!(
+            Answer::product(factors, Precision::Bits64),
+            Err(OverflowError {
+                precision: Precision::Bits64
+            })
+        );
+        assert_eq!(
+            Answer::product([u64::MAX, 2], Precision::Bits64),
+            Err(OverflowError {
+                precision: Precision::Bits64
+            })
+        );
+        assert_eq!(
+            Answer::product([i64::MIN as i128, 2], Precision::Bits64),
+            Err(OverflowError {
+                precision: Precision::Bits64
+            })
+        );
+        assert_eq!(
+            OverflowError {
+                precision: Precision::Bits64
+            }
+            .to_string(),
+            "answer does not fit in a signed or unsigned 64-bit integer"
+        );
+    }
+
+    #[test]
+    fn test_product_promotes() {
+        let factors = [u32::MAX as u64, u32::MAX as u64, 4];
+        let expected = 4 * (u32::MAX as i128) * (u32::MAX as i128);
+        assert_eq!(
+            Answer::product(factors, Precision::Bits128),
+            Ok(Answer::Wide(expected))
+        );
+        assert_eq!(
+            Answer::product([u64::MAX; 3], Precision::Bits128),
+            Err(OverflowError {
+                precision: Precision::Bits128
+            })
+        );
+        let big = Answer::product([u64::MAX; 3], Precision::Unbounded).expect("Never overflows");
+        assert_eq!(
+            big.to_string(),
+            "6277101735386680762814942322444851025767571854389858533375"
+        );
+    }
+
+    #[test]
+    fn test_product_demotes_when_it_fits_again() {
+        let factors = [u64::MAX, u64::MAX, 0];
+        assert_eq!(
+            Answer::product(factors, Precision::Unbounded),
+            Ok(Answer::Small(0))
+        );
+        assert_eq!(
+            Answer::product(factors, Precision::Bits64),
+            Err(OverflowError {
+                precision: Precision::Bits64
+            })
+        );
+    }
+}
@@ -0,0 +1,390 @@
+use std::{
+    cmp::Ordering,
+    convert::TryFrom,
+    fmt,
+    ops::{Add, Mul, Neg, Sub},
+    str::FromStr,
+};
+
+// Each limb holds nine decimal digits, which keeps parsing and printing
+// simple and leaves room for a carry in a u32.
+const BASE: u32 = 1_000_000_000;
+const BASE_DIGITS: usize = 9;
+
+// An arbitrary-precision signed integer. The magnitude is stored as base 10^9
+// limbs, least significant first, with no trailing zero limbs; zero is never
+// negative, so the derived Eq and Hash agree with numeric equality.
+#[derive(Clone, Debug, Default, Eq, Hash, PartialEq)]
+pub struct BigInt {
+    negative: bool,
+    limbs: Vec<u32>,
+}
+
+#[derive(Debug, Eq, PartialEq)]
+pub struct ParseBigIntError;
+
+#[derive(Debug, Eq, PartialEq)]
+pub struct TryFromBigIntError;
+
+impl fmt::Display for TryFromBigIntError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "out of range integral type conversion attempted")
+    }
+}
+
+impl std::error::Error for TryFromBigIntError {}
+
+impl fmt::Display for ParseBigIntError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "invalid digit found in string")
+    }
+}
+
+impl std::error::Error for ParseBigIntError {}
+
+impl BigInt {
+    pub fn zero() -> BigInt {
+        BigInt::default()
+    }
+
+    pub fn is_zero(&self) -> bool {
+        self.limbs.is_empty()
+    }
+
+    pub fn is_negative(&self) -> bool {
+        self.negative
+    }
+
+    fn from_parts(negative: bool, mut limbs: Vec<u32>) -> BigInt {
+        while limbs.last() == Some(&0) {
+            limbs.pop();
+        }
+        BigInt {
+            negative: negative && !limbs.is_empty(),
+            limbs,
+        }
+    }
+}
+
+fn compare_magnitude(a: &[u32], b: &[u32]) -> Ordering {
+    a.len()
+        .cmp(&b.len())
+        .then_with(|| a.iter().rev().cmp(b.iter().rev()))
+}
+
+fn add_magnitude(a: &[u32], b: &[u32]) -> Vec<u32> {
+    let mut limbs = Vec::with_capacity(a.len().max(b.len()) + 1);
+    let mut carry = 0;
+    for i in 0..a.len().max(b.len()) {
+        let sum = a.get(i).unwrap_or(&0) + b.get(i).unwrap_or(&0) + carry;
+        limbs.push(sum % BASE);
+        carry = sum / BASE;
+    }
+    if carry > 0 {
+        limbs.push(carry)
+    }
+    limbs
+}
+
+// Subtracts the smaller magnitude b from the larger magnitude a.
+fn sub_magnitude(a: &[u32], b: &[u32]) -> Vec<u32> {
+    let mut limbs = Vec::with_capacity(a.len());
+    let mut borrow = 0;
+    for (i, limb) in a.iter().enumerate() {
+        let subtrahend = b.get(i).unwrap_or(&0) + borrow;
+        if *limb >= subtrahend {
+            limbs.push(limb - subtrahend);
+            borrow = 0;
+        } else {
+            limbs.push(limb + BASE - subtrahend);
+            borrow = 1;
+        }
+    }
+    limbs
+}
+
+fn mul_magnitude(a: &[u32], b: &[u32]) -> Vec<u32> {
+    let mut limbs = vec![0u32; a.len() + b.len()];
+    for (i, x) in a.iter().enumerate() {
+        let mut carry = 0u64;
+        for (j, y) in b.iter().enumerate() {
+            // At most (10^9 - 1)^2 + 2 * (10^9 - 1), well inside a u64.
+            let product = *x as u64 * *y as u64 + limbs[i + j] as u64 + carry;
+            limbs[i + j] = (product % BASE as u64) as u32;
+            carry = product / BASE as u64;
+        }
+        limbs[i + b.len()] = carry as u32;
+    }
+    limbs
+}
+
+impl Ord for BigInt {
+    fn cmp(&self, other: &Self) -> Ordering {
+        match (self.negative, other.negative) {
+            (false, true) => Ordering::Greater,
+            (true, false) => Ordering::Less,
+            (false, false) => compare_magnitude(&self.limbs, &other.limbs),
+            (true, true) => compare_magnitude(&other.limbs, &self.limbs),
+        }
+    }
+}
+
+impl PartialOrd for BigInt {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Add for &BigInt {
+    type Output = BigInt;
+
+    fn add(self, other: &BigInt) -> BigInt {
+        if self.negative == other.negative {
+            return BigInt::from_parts(self.negative, add_magnitude(&self.limbs, &other.limbs));
+        }
+        match compare_magnitude(&self.limbs, &other.limbs) {
+            Ordering::Less => {
+                BigInt::from_parts(other.negative, sub_magnitude(&other.limbs, &self.limbs))
+            }
+            _ => BigInt::from_parts(self.negative, sub_magnitude(&self.limbs, &other.limbs)),
+        }
+    }
+}
+
+impl Add for BigInt {
+    type Output = BigInt;
+
+    fn add(self, other: BigInt) -> BigInt {
+        &self + &other
+    }
+}
+
+impl Neg for &BigInt {
+    type Output = BigInt;
+
+    fn neg(self) -> BigInt {
+        BigInt::from_parts(!self.negative, self.limbs.clone())
+    }
+}
+
+impl Neg for BigInt {
+    type Output = BigInt;
+
+    fn neg(self) -> BigInt {
+        BigInt::from_parts(!self.negative, self.limbs)
+    }
+}
+
+impl Sub for &BigInt {
+    type Output = BigInt;
+
+    fn sub(self, other: &BigInt) -> BigInt {
+        self + &(-other)
+    }
+}
+
+impl Sub for BigInt {
+    type Output = BigInt;
+
+    fn sub(self, other: BigInt) -> BigInt {
+        &self - &other
+    }
+}
+
+impl Mul for &BigInt {
+    type Output = BigInt;
+
+    fn mul(self, other: &BigInt) -> BigInt {
+        BigInt::from_parts(
+            self.negative != other.negative,
+            mul_magnitude(&self.limbs, &other.limbs),
+        )
+    }
+}
+
+impl Mul for BigInt {
+    type Output = BigInt;
+
+    fn mul(self, other: BigInt) -> BigInt {
+        &self * &other
+    }
+}
+
+impl From<i128> for BigInt {
+    fn from(number: i128) -> BigInt {
+        let mut magnitude = number.unsigned_abs();
+        let mut limbs = Vec::new();
+        while magnitude > 0 {
+            limbs.push((magnitude % BASE as u128) as u32);
+            magnitude /= BASE as u128;
+        }
+        BigInt::from_parts(number < 0, limbs)
+    }
+}
+
+impl From<i64> for BigInt {
+    fn from(number: i64) -> BigInt {
+        BigInt::from(number as i128)
+    }
+}
+
+impl TryFrom<&BigInt> for i128 {
+    type Error = TryFromBigIntError;
+
+    fn try_from(number: &BigInt) -> Result<i128, TryFromBigIntError> {
+        // Build the value negatively so i128::MIN, which has no positive
+        // counterpart, still converts.
+        let mut value: i128 = 0;
+        for limb in number.limbs.iter().rev() {
+            value = value
+                .checked_mul(BASE as i128)
+                .and_then(|v| v.checked_sub(*limb as i128))
+                .ok_or(TryFromBigIntError)?;
+        }
+        if number.negative {
+            Ok(value)
+        } else {
+            value.checked_neg().ok_or(TryFromBigIntError)
+        }
+    }
+}
+
+impl FromStr for BigInt {
+    type Err = ParseBigIntError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (negative, digits) = match s.strip_prefix('-') {
+            Some(rest) => (true, rest),
+            None => (false, s.strip_prefix('+').unwrap_or(s)),
+        };
+        if digits.is_empty() || !digits.bytes().all(|b| b.is_ascii_digit()) {
+            return Err(ParseBigIntError);
+        }
+        let mut limbs = Vec::with_capacity(digits.len() / BASE_DIGITS + 1);
+        let mut end = digits.len();
+        while end > 0 {
+            let start = end.saturating_sub(BASE_DIGITS);
+            limbs.push(
+                digits[start..end]
+                    .parse::<u32>()
+                    .map_err(|_| ParseBigIntError)?,
+            );
+            end = start;
+        }
+        Ok(BigInt::from_parts(negative, limbs))
+    }
+}
+
+impl fmt::Display for BigInt {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let mut limbs = self.limbs.iter().rev();
+        let mut output = String::new();
+        if self.negative {
+            output.push('-');
+        }
+        match limbs.next() {
+            Some(most_significant) => output.push_str(&most_significant.to_string()),
+            None => output.push('0'),
+        }
+        for limb in limbs {
+            output.push_str(&format!("{:09}", limb));
+        }
+        f.pad(&output)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn big(s: &str) -> BigInt {
+        s.parse().expect("Unable to parse number")
+    }
+
+    #[test]
+    fn test_parse_and_display() {
+        assert_eq!(big("0").to_string(), "0");
+        assert_eq!(big("-0").to_string(), "0");
+        assert_eq!(big("+42").to_string(), "42");
+        assert_eq!(big("000001000000000").to_string(), "1000000000");
+        assert_eq!(
+            big("-123456789012345678901234567890").to_string(),
+            "-123456789012345678901234567890"
+        );
+        assert_eq!("12a".parse::<BigInt>(), Err(ParseBigIntError));
+        assert_eq!("-".parse::<BigInt>(), Err(ParseBigIntError));
+    }
+
+    #[test]
+    fn test_from_integers() {
+        assert_eq!(BigInt::from(i64::MIN).to_string(), i64::MIN.to_string());
+        assert_eq!(BigInt::from(i128::MAX).to_string(), i128::MAX.to_string());
+        assert_eq!(BigInt::from(0i64), BigInt::zero());
+    }
+
+    #[test]
+    fn test_add_and_sub() {
+        let max = BigInt::from(i64::MAX);
+        assert_eq!((&max + &max).to_string(), "18446744073709551614");
+        assert_eq!(big("999999999") + big("1"), big("1000000000"));
+        assert_eq!(big("1000000000") - big("1"), big("999999999"));
+        assert_eq!(big("5") - big("12"), big("-7"));
+        assert_eq!(big("-5") + big("12"), big("7"));
+        assert_eq!(big("-5") - big("-5"), BigInt::zero());
+    }
+
+    #[test]
+    fn test_mul() {
+        let max = BigInt::from(i128::MAX);
+        assert_eq!(
+            (&max * &max).to_string(),
+            "28948022309329048855892746252171976962977213799489202546401021394546514198529"
+        );
+        assert_eq!(
+            big("999999999") * big("999999999"),
+            big("999999998000000001")
+        );
+        assert_eq!(big("-12") * big("5"), big("-60"));
+        assert_eq!(big("-12") * big("-5"), big("60"));
+        assert_eq!(big("-12") * BigInt::zero(), BigInt::zero());
+        assert!(!(big("-12") * BigInt::zero()).is_negative());
+    }
+
+    #[test]
+    fn test_try_into_i128() {
+        assert_eq!(i128::try_from(&BigInt::from(i128::MAX)), Ok(i128::MAX));
+        assert_eq!(i128::try_from(&BigInt::from(i128::MIN)), Ok(i128::MIN));
+        assert_eq!(i128::try_from(&big("-42")), Ok(-42));
+        assert_eq!(i128::try_from(&BigInt::zero()), Ok(0));
+        assert_eq!(
+            i128::try_from(&(BigInt::from(i128::MAX) + big("1"))),
+            Err(TryFromBigIntError)
+        );
+        assert_eq!(
+            i128::try_from(&(BigInt::from(i128::MIN) - big("1"))),
+            Err(TryFromBigIntError)
+        );
+    }
+
+    #[test]
+    fn test_ordering() {
+        let mut numbers = vec![
+            big("3"),
+            big("-20"),
+            big("1000000000000"),
+            big("-1"),
+            big("0"),
+        ];
+        numbers.sort();
+        assert_eq!(
+            numbers,
+            vec![
+                big("-20"),
+                big("-1"),
+                big("0"),
+                big("3"),
+                big("1000000000000")
+            ]
+        )
+    }
+}
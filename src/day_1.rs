@@ -20,11 +20,7 @@ pub fn find_three_items_that_sum_2020(input: &[u32]) -> Option<Vec<u32>> {
         for j in input {
             for k in input {
                 if i + j + k == 2020 {
-                    let mut numbers = Vec::new();
-                    numbers.push(*i);
-                    numbers.push(*j);
-                    numbers.push(*k);
-                    return Some(numbers);
+                    return Some(vec![*i, *j, *k]);
                 }
             }
         }
@@ -1,18 +1,31 @@
 use std::{
+    collections::HashSet,
+    error::Error,
+    fmt,
     fs::File,
     io,
     io::{BufRead, BufReader},
 };
+use unicode_segmentation::UnicodeSegmentation;
 
-#[derive(Eq, PartialEq, Debug)]
-struct Policy {
-    min: u32,
-    max: u32,
-    target_char: char,
+mod parallel;
+mod report;
+mod rules;
+
+pub use parallel::{count_valid_passwords_in_file, count_valid_passwords_parallel, CountError};
+pub use report::{validation_report, write_report_csv, LineReport, ParsedLine, Verdict};
+pub use rules::{Rule, RuleParseError};
+
+// The numbers and character given in front of each password.
+#[derive(Clone, Copy, Eq, PartialEq, Debug)]
+pub struct Policy {
+    pub min: u32,
+    pub max: u32,
+    pub target_char: char,
 }
 
 impl Policy {
-    fn new(min: u32, max: u32, target_char: char) -> Policy {
+    pub fn new(min: u32, max: u32, target_char: char) -> Policy {
         Policy {
             min,
             max,
@@ -21,23 +34,132 @@ impl Policy {
     }
 }
 
-fn parse_password_policy(input: &str) -> Policy {
-    let split_input = input.split_whitespace().collect::<Vec<&str>>();
-    let number_input = split_input.get(0).expect("No number input found");
-    let min_max_split = number_input.split_terminator('-').collect::<Vec<&str>>();
-    let min = extract_number(&min_max_split, 0);
-    let max = extract_number(&min_max_split, 1);
-    let char_input = split_input.get(1).expect("No character input found");
-    let target_char = extract_char_in_position(char_input, 1);
-    Policy::new(min, max, target_char)
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum ParseReason {
+    ExpectedNumber,
+    NumberTooLarge,
+    ExpectedDash,
+    ExpectedSpace,
+    ExpectedCharacter,
+    ExpectedColon,
+    MissingPassword,
+}
+
+impl fmt::Display for ParseReason {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let reason = match self {
+            ParseReason::ExpectedNumber => "expected a number",
+            ParseReason::NumberTooLarge => "number is too large",
+            ParseReason::ExpectedDash => "expected '-' between the numbers",
+            ParseReason::ExpectedSpace => "expected a space before the character",
+            ParseReason::ExpectedCharacter => "expected a single character",
+            ParseReason::ExpectedColon => "expected ':' after the character",
+            ParseReason::MissingPassword => "no password after ':'",
+        };
+        write!(f, "{}", reason)
+    }
+}
+
+// Where and why a line could not be parsed. Columns count characters from 1.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct ParseError {
+    pub column: usize,
+    pub reason: ParseReason,
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "column {}: {}", self.column, self.reason)
+    }
+}
+
+impl Error for ParseError {}
+
+// Walks a line one character at a time so errors can point at a column.
+struct LineParser {
+    chars: Vec<char>,
+    position: usize,
+}
+
+impl LineParser {
+    fn new(input: &str) -> LineParser {
+        LineParser {
+            chars: input.chars().collect(),
+            position: 0,
+        }
+    }
+
+    fn error(&self, reason: ParseReason) -> ParseError {
+        ParseError {
+            column: self.position + 1,
+            reason,
+        }
+    }
+
+    fn peek(&self) -> Option<char> {
+        self.chars.get(self.position).copied()
+    }
+
+    fn skip_whitespace(&mut self) -> usize {
+        let start = self.position;
+        while self.peek().is_some_and(char::is_whitespace) {
+            self.position += 1;
+        }
+        self.position - start
+    }
+
+    fn expect(&mut self, expected: char, reason: ParseReason) -> Result<(), ParseError> {
+        if self.peek() == Some(expected) {
+            self.position += 1;
+            Ok(())
+        } else {
+            Err(self.error(reason))
+        }
+    }
+
+    fn number(&mut self) -> Result<u32, ParseError> {
+        let start = self.position;
+        while self.peek().is_some_and(|c| c.is_ascii_digit()) {
+            self.position += 1;
+        }
+        if start == self.position {
+            return Err(self.error(ParseReason::ExpectedNumber));
+        }
+        self.chars[start..self.position]
+            .iter()
+            .collect::<String>()
+            .parse::<u32>()
+            .map_err(|_| ParseError {
+                column: start + 1,
+                reason: ParseReason::NumberTooLarge,
+            })
+    }
+
+    fn policy(&mut self) -> Result<Policy, ParseError> {
+        self.skip_whitespace();
+        let min = self.number()?;
+        self.expect('-', ParseReason::ExpectedDash)?;
+        let max = self.number()?;
+        if self.skip_whitespace() == 0 {
+            return Err(self.error(ParseReason::ExpectedSpace));
+        }
+        let target_char = match self.peek() {
+            Some(c) if c != ':' && !c.is_whitespace() => c,
+            _ => return Err(self.error(ParseReason::ExpectedCharacter)),
+        };
+        self.position += 1;
+        Ok(Policy::new(min, max, target_char))
+    }
 }
 
-fn extract_number(input: &[&str], position: u32) -> u32 {
-    input
-        .get(position as usize)
-        .expect("No number found")
-        .parse::<u32>()
-        .expect("Could not parse number")
+fn parse_password_policy(input: &str) -> Result<Policy, ParseError> {
+    let mut parser = LineParser::new(input);
+    let policy = parser.policy()?;
+    parser.skip_whitespace();
+    match parser.peek() {
+        Some(_) => Err(parser.error(ParseReason::ExpectedColon)),
+        None => Ok(policy),
+    }
 }
 
 fn min_max_strategy(policy: &Policy, password: &str) -> bool {
@@ -48,11 +170,32 @@ fn min_max_strategy(policy: &Policy, password: &str) -> bool {
     count >= policy.min && count <= policy.max
 }
 
-fn position_strategy(policy: &Policy, password: &str) -> bool {
-    let first_position = extract_char_in_position(password, policy.min);
-    let second_position = extract_char_in_position(password, policy.max);
-    let first_match = first_position == policy.target_char;
-    let second_match = second_position == policy.target_char;
+fn min_max_failure(policy: &Policy, password: &str) -> Option<String> {
+    if min_max_strategy(policy, password) {
+        return None;
+    }
+    let count = password
+        .chars()
+        .filter(|c| *c == policy.target_char)
+        .count();
+    Some(format!(
+        "found {} '{}', allowed {}\u{2013}{}",
+        count, policy.target_char, policy.min, policy.max
+    ))
+}
+
+// How positions in a password are counted. Char counts Unicode scalar
+// values; Grapheme counts extended grapheme clusters, so an accent written as
+// a combining mark or an emoji with a skin tone takes up a single position.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum PositionMode {
+    Char,
+    Grapheme,
+}
+
+fn position_strategy(policy: &Policy, password: &str, mode: PositionMode) -> bool {
+    let first_match = position_matches(password, policy.min, policy.target_char, mode);
+    let second_match = position_matches(password, policy.max, policy.target_char, mode);
     if first_match && second_match {
         false
     } else {
@@ -60,44 +203,234 @@ fn position_strategy(policy: &Policy, password: &str) -> bool {
     }
 }
 
-fn extract_char_in_position(input: &str, position: u32) -> char {
-    let range = (position - 1) as usize..position as usize;
-    *input
-        .get(range)
-        .expect("No char input found")
-        .chars()
-        .collect::<Vec<char>>()
-        .first()
-        .expect("Empty vec")
+fn position_failure(policy: &Policy, password: &str, mode: PositionMode) -> Option<String> {
+    if position_strategy(policy, password, mode) {
+        return None;
+    }
+    let describe = |position| {
+        let found = match mode {
+            PositionMode::Char => extract_char_in_position(password, position).map(String::from),
+            PositionMode::Grapheme => {
+                extract_grapheme_in_position(password, position).map(String::from)
+            }
+        };
+        match found {
+            Some(found) => format!("position {} is '{}'", position, found),
+            None => format!("position {} is out of range", position),
+        }
+    };
+    Some(format!(
+        "{} and {}, expected exactly one '{}'",
+        describe(policy.min),
+        describe(policy.max),
+        policy.target_char
+    ))
+}
+
+// Positions count from 1. A position of 0 or past the end of the password
+// never matches.
+fn position_matches(input: &str, position: u32, target_char: char, mode: PositionMode) -> bool {
+    match mode {
+        PositionMode::Char => extract_char_in_position(input, position) == Some(target_char),
+        PositionMode::Grapheme => {
+            let mut target = [0; 4];
+            extract_grapheme_in_position(input, position)
+                == Some(target_char.encode_utf8(&mut target))
+        }
+    }
 }
 
-fn split_input_string(input: &str) -> (Policy, String) {
-    let split_input = input.split_terminator(':').collect::<Vec<&str>>();
-    let policy = split_input.get(0).expect("No policy string");
-    let password = split_input.get(1).expect("No password string").trim();
+fn extract_char_in_position(input: &str, position: u32) -> Option<char> {
+    let index = (position as usize).checked_sub(1)?;
+    input.chars().nth(index)
+}
+
+fn extract_grapheme_in_position(input: &str, position: u32) -> Option<&str> {
+    let index = (position as usize).checked_sub(1)?;
+    input.graphemes(true).nth(index)
+}
 
-    (parse_password_policy(&policy), password.to_string())
+// Splits a line into its policy and password. Only the first ':' ends the
+// policy, so a password may contain colons of its own.
+fn split_input_string(input: &str) -> Result<(Policy, String), ParseError> {
+    let end_column = input.chars().count() + 1;
+    let (policy, password) = match input.split_once(':') {
+        Some((policy, password)) => (policy, password),
+        None => {
+            parse_password_policy(input)?;
+            return Err(ParseError {
+                column: end_column,
+                reason: ParseReason::ExpectedColon,
+            });
+        }
+    };
+    let policy = parse_password_policy(policy)?;
+    let password = password.trim();
+    if password.is_empty() {
+        return Err(ParseError {
+            column: end_column,
+            reason: ParseReason::MissingPassword,
+        });
+    }
+    Ok((policy, password.to_string()))
 }
 
-fn interpret_input_line(input: &str, strategy: fn(&Policy, &str) -> bool) -> bool {
-    let (policy, password) = split_input_string(input);
-    strategy(&policy, &password)
+fn interpret_input_line<P>(input: &str, password_policy: &P) -> Result<bool, ParseError>
+where
+    P: PasswordPolicy + ?Sized,
+{
+    let (policy, password) = split_input_string(input)?;
+    Ok(password_policy.is_valid(&policy, &password))
 }
 
+// A rule that a password has to satisfy. The policy parsed from the same line
+// is passed in, so a rule can use its numbers and character or ignore them.
+pub trait PasswordPolicy {
+    fn is_valid(&self, policy: &Policy, password: &str) -> bool;
+
+    // Says why the password fails, or None if it passes. Rules that have
+    // nothing more specific to say fall back to a general message.
+    fn failure_reason(&self, policy: &Policy, password: &str) -> Option<String> {
+        if self.is_valid(policy, password) {
+            None
+        } else {
+            Some("does not satisfy the policy".to_string())
+        }
+    }
+}
+
+impl<F> PasswordPolicy for F
+where
+    F: Fn(&Policy, &str) -> bool,
+{
+    fn is_valid(&self, policy: &Policy, password: &str) -> bool {
+        self(policy, password)
+    }
+}
+
+// The two rules from the puzzle. Position counts characters; GraphemePosition
+// is the same rule counting user-perceived characters instead.
 pub enum PolicyStrategy {
     MinMax,
     Position,
+    GraphemePosition,
 }
 
-pub fn number_of_valid_passwords(input: &[String], policy_strategy: PolicyStrategy) -> u32 {
-    let strategy = match policy_strategy {
-        PolicyStrategy::MinMax => min_max_strategy,
-        PolicyStrategy::Position => position_strategy,
-    };
-    input
-        .iter()
-        .filter(|line| interpret_input_line(line, strategy))
-        .count() as u32
+impl PasswordPolicy for PolicyStrategy {
+    fn is_valid(&self, policy: &Policy, password: &str) -> bool {
+        match self {
+            PolicyStrategy::MinMax => min_max_strategy(policy, password),
+            PolicyStrategy::Position => position_strategy(policy, password, PositionMode::Char),
+            PolicyStrategy::GraphemePosition => {
+                position_strategy(policy, password, PositionMode::Grapheme)
+            }
+        }
+    }
+
+    fn failure_reason(&self, policy: &Policy, password: &str) -> Option<String> {
+        match self {
+            PolicyStrategy::MinMax => min_max_failure(policy, password),
+            PolicyStrategy::Position => position_failure(policy, password, PositionMode::Char),
+            PolicyStrategy::GraphemePosition => {
+                position_failure(policy, password, PositionMode::Grapheme)
+            }
+        }
+    }
+}
+
+// The policy character must not appear anywhere in the password.
+pub struct CharacterAbsent;
+
+impl PasswordPolicy for CharacterAbsent {
+    fn is_valid(&self, policy: &Policy, password: &str) -> bool {
+        !password.contains(policy.target_char)
+    }
+}
+
+// The password must use at least this many different characters.
+pub struct DistinctCharacters(pub usize);
+
+impl PasswordPolicy for DistinctCharacters {
+    fn is_valid(&self, _policy: &Policy, password: &str) -> bool {
+        password.chars().collect::<HashSet<char>>().len() >= self.0
+    }
+}
+
+// No character may repeat more than this many times in a row.
+pub struct MaxRunLength(pub usize);
+
+impl PasswordPolicy for MaxRunLength {
+    fn is_valid(&self, _policy: &Policy, password: &str) -> bool {
+        let mut previous = None;
+        let mut run = 0;
+        for c in password.chars() {
+            run = if previous == Some(c) { run + 1 } else { 1 };
+            if run > self.0 {
+                return false;
+            }
+            previous = Some(c);
+        }
+        true
+    }
+}
+
+// What to do with a line that cannot be parsed.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum ErrorMode {
+    Skip,
+    Abort,
+}
+
+// A parse error along with the line it was found on, counting from 1.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct LineError {
+    pub line: usize,
+    pub error: ParseError,
+}
+
+impl fmt::Display for LineError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "line {}, {}", self.line, self.error)
+    }
+}
+
+impl Error for LineError {}
+
+#[derive(Debug, Default, Eq, PartialEq)]
+pub struct PasswordCount {
+    pub valid: u32,
+    pub skipped: Vec<LineError>,
+}
+
+// Counts the lines whose password satisfies the password policy. With ErrorMode::Skip
+// unparseable lines are left out of the count and listed in the result;
+// with ErrorMode::Abort the first one is returned as the error.
+pub fn number_of_valid_passwords<P>(
+    input: &[String],
+    password_policy: &P,
+    error_mode: ErrorMode,
+) -> Result<PasswordCount, LineError>
+where
+    P: PasswordPolicy + ?Sized,
+{
+    let mut count = PasswordCount::default();
+    for (idx, line) in input.iter().enumerate() {
+        match interpret_input_line(line, password_policy) {
+            Ok(true) => count.valid += 1,
+            Ok(false) => (),
+            Err(error) => {
+                let line_error = LineError {
+                    line: idx + 1,
+                    error,
+                };
+                match error_mode {
+                    ErrorMode::Skip => count.skipped.push(line_error),
+                    ErrorMode::Abort => return Err(line_error),
+                }
+            }
+        }
+    }
+    Ok(count)
 }
 
 pub fn load_input_file(file_name: &str) -> io::Result<Vec<String>> {
@@ -119,40 +452,158 @@ mod test {
     fn test_parse_password_policy() {
         let correct_policy = Policy::new(1, 3, 'a');
         let input = "1-3 a";
-        assert_eq!(correct_policy, parse_password_policy(input));
+        assert_eq!(Ok(correct_policy), parse_password_policy(input));
     }
 
     #[test]
     fn test_parse_password_policy_two_digit_numbers() {
         let correct_policy = Policy::new(10, 12, 'a');
         let input = "10-12 a";
-        assert_eq!(correct_policy, parse_password_policy(input));
+        assert_eq!(Ok(correct_policy), parse_password_policy(input));
+    }
+
+    #[test]
+    fn test_parse_password_policy_errors() {
+        let error = |column, reason| Err(ParseError { column, reason });
+        assert_eq!(
+            parse_password_policy("3-a b"),
+            error(3, ParseReason::ExpectedNumber)
+        );
+        assert_eq!(
+            parse_password_policy("3 4 b"),
+            error(2, ParseReason::ExpectedDash)
+        );
+        assert_eq!(
+            parse_password_policy("3-4b"),
+            error(4, ParseReason::ExpectedSpace)
+        );
+        assert_eq!(
+            parse_password_policy("3-4 "),
+            error(5, ParseReason::ExpectedCharacter)
+        );
+        assert_eq!(
+            parse_password_policy("99999999999-4 b"),
+            error(1, ParseReason::NumberTooLarge)
+        );
+        assert_eq!(
+            parse_password_policy("1-3 ab"),
+            error(6, ParseReason::ExpectedColon)
+        );
     }
 
     #[test]
     fn test_min_max_strategy() {
         let policy = Policy::new(1, 3, 'a');
         let password = "abcde";
-        assert_eq!(true, min_max_strategy(&policy, password));
+        assert!(min_max_strategy(&policy, password));
         let policy = Policy::new(1, 3, 'b');
         let password = "cdefg";
-        assert_eq!(false, min_max_strategy(&policy, password))
+        assert!(!min_max_strategy(&policy, password))
     }
 
     #[test]
     fn test_position_strategy() {
         let policy = Policy::new(1, 3, 'a');
         let password = "abcde";
-        assert_eq!(true, position_strategy(&policy, password));
+        assert!(position_strategy(&policy, password, PositionMode::Char));
         let policy = Policy::new(1, 3, 'b');
         let password = "cdefg";
-        assert_eq!(false, position_strategy(&policy, password));
+        assert!(!position_strategy(&policy, password, PositionMode::Char));
         let policy = Policy::new(2, 9, 'c');
         let password = "ccccccccc";
-        assert_eq!(false, position_strategy(&policy, password));
+        assert!(!position_strategy(&policy, password, PositionMode::Char));
         let policy = Policy::new(2, 9, 'd');
         let password = "cccdccccd";
-        assert_eq!(true, position_strategy(&policy, password))
+        assert!(position_strategy(&policy, password, PositionMode::Char))
+    }
+
+    #[test]
+    fn test_position_strategy_out_of_range() {
+        let policy = Policy::new(0, 3, 'a');
+        assert!(position_strategy(&policy, "bca", PositionMode::Char));
+        let policy = Policy::new(0, 2, 'a');
+        assert!(!position_strategy(&policy, "bca", PositionMode::Char));
+        let policy = Policy::new(1, 9, 'a');
+        assert!(position_strategy(&policy, "abc", PositionMode::Char));
+        assert!(!position_strategy(&policy, "", PositionMode::Grapheme))
+    }
+
+    #[test]
+    fn test_position_strategy_accented_password() {
+        let policy = Policy::new(2, 4, '\u{e9}');
+        assert!(position_strategy(&policy, "c\u{e9}afe", PositionMode::Char));
+        assert!(position_strategy(
+            &policy,
+            "c\u{e9}afe",
+            PositionMode::Grapheme
+        ));
+        let decomposed = "e\u{301}ab";
+        let policy = Policy::new(2, 5, 'a');
+        assert!(!position_strategy(&policy, decomposed, PositionMode::Char));
+        assert!(position_strategy(
+            &policy,
+            decomposed,
+            PositionMode::Grapheme
+        ))
+    }
+
+    #[test]
+    fn test_position_strategy_emoji_password() {
+        let policy = Policy::new(2, 3, 'a');
+        assert!(position_strategy(
+            &policy,
+            "\u{1F600}a\u{1F600}",
+            PositionMode::Char
+        ));
+        let policy = Policy::new(2, 4, 'a');
+        let skin_tone = "\u{1F44D}\u{1F3FD}ab";
+        assert!(!position_strategy(&policy, skin_tone, PositionMode::Char));
+        assert!(position_strategy(
+            &policy,
+            skin_tone,
+            PositionMode::Grapheme
+        ));
+        let policy = Policy::new(1, 2, '\u{1F600}');
+        assert!(!position_strategy(
+            &policy,
+            "\u{1F600}\u{1F600}\u{1F600}",
+            PositionMode::Char
+        ));
+        assert!(position_strategy(
+            &policy,
+            "\u{1F600}x",
+            PositionMode::Grapheme
+        ))
+    }
+
+    #[test]
+    fn test_number_of_valid_passwords_with_unicode() {
+        let input = Vec::from([
+            "1-2 \u{e9}: \u{e9}t\u{e9}".to_string(),
+            "2-4 a: \u{1F44D}\u{1F3FD}ab".to_string(),
+            "4-12 z: z".to_string(),
+        ]);
+        let count = number_of_valid_passwords(&input, &PolicyStrategy::Position, ErrorMode::Abort);
+        assert_eq!(Ok(1), count.map(|c| c.valid));
+        let count =
+            number_of_valid_passwords(&input, &PolicyStrategy::GraphemePosition, ErrorMode::Abort);
+        assert_eq!(Ok(2), count.map(|c| c.valid))
+    }
+
+    #[test]
+    fn test_grapheme_position_in_other_scripts() {
+        let input = Vec::from([
+            // A Hangul syllable written as two jamo.
+            "1-2 x: \u{1100}\u{1161}x".to_string(),
+            // A Bengali letter with a vowel sign.
+            "1-2 x: \u{0995}\u{09BE}x".to_string(),
+            // Flags pair up, so neither position holds a lone indicator.
+            "1-2 \u{1F1EC}: \u{1F1EC}\u{1F1E7}\u{1F1EB}".to_string(),
+            "1-2 x: \r\nx".to_string(),
+        ]);
+        let count =
+            number_of_valid_passwords(&input, &PolicyStrategy::GraphemePosition, ErrorMode::Abort);
+        assert_eq!(Ok(3), count.map(|c| c.valid))
     }
 
     #[test]
@@ -160,16 +611,37 @@ mod test {
         let input = "1-3 a: abcde";
         let policy = Policy::new(1, 3, 'a');
         let password = "abcde";
-        assert_eq!((policy, password.into()), split_input_string(input))
+        assert_eq!(Ok((policy, password.into())), split_input_string(input))
     }
 
     #[test]
-    fn test_intepret_input_line_with_min_max() {
-        let input = "1-3 a: abcde";
+    fn test_split_input_string_password_with_colon() {
+        let input = "1-3 a: ab:c:de";
+        let policy = Policy::new(1, 3, 'a');
+        assert_eq!(Ok((policy, "ab:c:de".into())), split_input_string(input))
+    }
+
+    #[test]
+    fn test_split_input_string_errors() {
+        let error = |column, reason| Err(ParseError { column, reason });
+        assert_eq!(
+            split_input_string("1-3 a abcde"),
+            error(7, ParseReason::ExpectedColon)
+        );
         assert_eq!(
-            true,
-            interpret_input_line(input, |policy, password| min_max_strategy(policy, password))
+            split_input_string("1-3 a:  "),
+            error(9, ParseReason::MissingPassword)
         );
+        assert_eq!(
+            split_input_string(""),
+            error(1, ParseReason::ExpectedNumber)
+        );
+    }
+
+    #[test]
+    fn test_intepret_input_line_with_min_max() {
+        let input = "1-3 a: abcde";
+        assert_eq!(interpret_input_line(input, &min_max_strategy), Ok(true));
     }
 
     #[test]
@@ -179,22 +651,58 @@ mod test {
             "1-3 b: cdefg".to_string(),
             "2-9 c: ccccccccc".to_string(),
         ]);
-        assert_eq!(2, number_of_valid_passwords(&input, PolicyStrategy::MinMax))
+        let count = number_of_valid_passwords(&input, &PolicyStrategy::MinMax, ErrorMode::Abort);
+        assert_eq!(Ok(2), count.map(|c| c.valid))
     }
 
     #[test]
-    fn test_number_of_valid_passwords_with_position() {
+    fn test_number_of_valid_passwords_with_bad_lines() {
         let input = Vec::from([
             "1-3 a: abcde".to_string(),
-            "1-3 b: cdefg".to_string(),
+            "3-a b: xyz".to_string(),
             "2-9 c: ccccccccc".to_string(),
+            "1-3 b".to_string(),
         ]);
+        let skipped = Vec::from([
+            LineError {
+                line: 2,
+                error: ParseError {
+                    column: 3,
+                    reason: ParseReason::ExpectedNumber,
+                },
+            },
+            LineError {
+                line: 4,
+                error: ParseError {
+                    column: 6,
+                    reason: ParseReason::ExpectedColon,
+                },
+            },
+        ]);
+        assert_eq!(
+            number_of_valid_passwords(&input, &PolicyStrategy::MinMax, ErrorMode::Skip),
+            Ok(PasswordCount {
+                valid: 2,
+                skipped: skipped.clone()
+            })
+        );
         assert_eq!(
-            1,
-            number_of_valid_passwords(&input, PolicyStrategy::Position)
+            number_of_valid_passwords(&input, &PolicyStrategy::MinMax, ErrorMode::Abort),
+            Err(skipped[0])
         )
     }
 
+    #[test]
+    fn test_number_of_valid_passwords_with_position() {
+        let input = Vec::from([
+            "1-3 a: abcde".to_string(),
+            "1-3 b: cdefg".to_string(),
+            "2-9 c: ccccccccc".to_string(),
+        ]);
+        let count = number_of_valid_passwords(&input, &PolicyStrategy::Position, ErrorMode::Abort);
+        assert_eq!(Ok(1), count.map(|c| c.valid))
+    }
+
     #[test]
     fn test_load_input_file() {
         let input = load_input_file("day_2_test.txt").expect("Unable to load the file");
@@ -205,4 +713,49 @@ mod test {
         ]);
         assert_eq!(correct_list, input)
     }
+
+    #[test]
+    fn test_character_absent() {
+        let policy = Policy::new(1, 3, 'a');
+        assert!(CharacterAbsent.is_valid(&policy, "bcde"));
+        assert!(!CharacterAbsent.is_valid(&policy, "bcade"))
+    }
+
+    #[test]
+    fn test_distinct_characters() {
+        let policy = Policy::new(1, 3, 'a');
+        assert!(DistinctCharacters(3).is_valid(&policy, "abcabc"));
+        assert!(!DistinctCharacters(4).is_valid(&policy, "abcabc"))
+    }
+
+    #[test]
+    fn test_max_run_length() {
+        let policy = Policy::new(1, 3, 'a');
+        assert!(MaxRunLength(2).is_valid(&policy, "aabbaa"));
+        assert!(!MaxRunLength(2).is_valid(&policy, "abbba"));
+        assert!(MaxRunLength(0).is_valid(&policy, ""))
+    }
+
+    #[test]
+    fn test_number_of_valid_passwords_with_custom_policy() {
+        let input = Vec::from([
+            "1-3 a: abcde".to_string(),
+            "1-3 b: cdefg".to_string(),
+            "2-9 c: ccccccccc".to_string(),
+        ]);
+        let long_enough = |policy: &Policy, password: &str| password.len() as u32 > policy.max;
+        let count = number_of_valid_passwords(&input, &long_enough, ErrorMode::Abort);
+        assert_eq!(Ok(2), count.map(|c| c.valid));
+        let rules: Vec<Box<dyn PasswordPolicy>> = Vec::from([
+            Box::new(CharacterAbsent) as Box<dyn PasswordPolicy>,
+            Box::new(MaxRunLength(1)),
+        ]);
+        let counts = rules
+            .iter()
+            .map(|rule| {
+                number_of_valid_passwords(&input, rule.as_ref(), ErrorMode::Abort).map(|c| c.valid)
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(counts, Vec::from([Ok(1), Ok(2)]))
+    }
 }
@@ -0,0 +1,340 @@
+use super::{interpret_input_line, ErrorMode, LineError, PasswordCount, PasswordPolicy};
+use std::{
+    error::Error,
+    fmt,
+    fs::File,
+    io::{self, BufRead, BufReader},
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        mpsc::{sync_channel, Receiver},
+        Mutex,
+    },
+    thread,
+};
+
+// Roughly how many bytes of input are handed to a worker at a time. Chunks
+// always end on a line break, so they may run slightly over.
+const CHUNK_BYTES: usize = 1 << 20;
+
+// Why a streamed count stopped: the input could not be read, or a line could
+// not be parsed under ErrorMode::Abort.
+#[derive(Debug)]
+pub enum CountError {
+    Io(io::Error),
+    Line(LineError),
+}
+
+impl fmt::Display for CountError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            CountError::Io(e) => write!(f, "unable to read input: {}", e),
+            CountError::Line(e) => write!(f, "unable to parse {}", e),
+        }
+    }
+}
+
+impl Error for CountError {}
+
+impl From<io::Error> for CountError {
+    fn from(e: io::Error) -> Self {
+        CountError::Io(e)
+    }
+}
+
+// A run of whole lines, left as raw bytes so the reading thread only has to
+// find line breaks and the workers do the decoding.
+struct Chunk {
+    first_line: usize,
+    bytes: Vec<u8>,
+}
+
+fn count_lines(bytes: &[u8]) -> usize {
+    let breaks = bytes.iter().filter(|&&b| b == b'\n').count();
+    match bytes.last() {
+        Some(b'\n') | None => breaks,
+        Some(_) => breaks + 1,
+    }
+}
+
+fn count_chunk<P>(chunk: &Chunk, password_policy: &P) -> io::Result<PasswordCount>
+where
+    P: PasswordPolicy + ?Sized,
+{
+    let text = std::str::from_utf8(&chunk.bytes).map_err(|_| {
+        io::Error::new(
+            io::ErrorKind::InvalidData,
+            "stream did not contain valid UTF-8",
+        )
+    })?;
+    let mut count = PasswordCount::default();
+    for (idx, line) in text.lines().enumerate() {
+        match interpret_input_line(line, password_policy) {
+            Ok(true) => count.valid += 1,
+            Ok(false) => (),
+            Err(error) => count.skipped.push(LineError {
+                line: chunk.first_line + idx,
+                error,
+            }),
+        }
+    }
+    Ok(count)
+}
+
+fn worker<P>(
+    chunks: &Mutex<Receiver<Chunk>>,
+    password_policy: &P,
+    failed: &AtomicBool,
+) -> io::Result<PasswordCount>
+where
+    P: PasswordPolicy + Sync + ?Sized,
+{
+    let mut total = Ok(PasswordCount::default());
+    loop {
+        let chunk = match chunks.lock().expect("A worker panicked").recv() {
+            Ok(chunk) => chunk,
+            Err(_) => return total,
+        };
+        // Keep draining after a failure so the reader is never left blocked.
+        match (&mut total, count_chunk(&chunk, password_policy)) {
+            (Ok(total), Ok(count)) => {
+                if !count.skipped.is_empty() {
+                    failed.store(true, Ordering::Relaxed);
+                }
+                total.valid += count.valid;
+                total.skipped.extend(count.skipped);
+            }
+            (Ok(_), Err(e)) => {
+                failed.store(true, Ordering::Relaxed);
+                total = Err(e);
+            }
+            (Err(_), _) => (),
+        }
+    }
+}
+
+// Counts valid passwords streamed from a reader, spreading chunks of lines
+// across worker threads. Only a few chunks are held in memory at once, and
+// the result is the same as number_of_valid_passwords on the whole input:
+// skipped lines are reported in line order, and under ErrorMode::Abort the
+// error is the one on the earliest bad line. A thread count of 0 uses one
+// thread per available core.
+pub fn count_valid_passwords_parallel<P, R>(
+    reader: R,
+    password_policy: &P,
+    error_mode: ErrorMode,
+    threads: usize,
+) -> Result<PasswordCount, CountError>
+where
+    P: PasswordPolicy + Sync + ?Sized,
+    R: BufRead,
+{
+    count_in_chunks(reader, password_policy, error_mode, threads, CHUNK_BYTES)
+}
+
+fn count_in_chunks<P, R>(
+    mut reader: R,
+    password_policy: &P,
+    error_mode: ErrorMode,
+    threads: usize,
+    chunk_bytes: usize,
+) -> Result<PasswordCount, CountError>
+where
+    P: PasswordPolicy + Sync + ?Sized,
+    R: BufRead,
+{
+    let threads = match threads {
+        0 => thread::available_parallelism().map_or(1, |n| n.get()),
+        n => n,
+    };
+    let (sender, receiver) = sync_channel::<Chunk>(threads * 2);
+    let receiver = Mutex::new(receiver);
+    let failed = AtomicBool::new(false);
+
+    let (read_result, counts) = thread::scope(|scope| {
+        let workers = (0..threads)
+            .map(|_| scope.spawn(|| worker(&receiver, password_policy, &failed)))
+            .collect::<Vec<_>>();
+
+        let mut read_chunks = || -> io::Result<()> {
+            let mut first_line = 1;
+            loop {
+                let mut bytes = Vec::with_capacity(chunk_bytes);
+                while bytes.len() < chunk_bytes && reader.read_until(b'\n', &mut bytes)? > 0 {}
+                if bytes.is_empty() {
+                    return Ok(());
+                }
+                let next_line = first_line + count_lines(&bytes);
+                if sender.send(Chunk { first_line, bytes }).is_err() {
+                    return Ok(());
+                }
+                first_line = next_line;
+                if error_mode == ErrorMode::Abort && failed.load(Ordering::Relaxed) {
+                    return Ok(());
+                }
+            }
+        };
+        let read_result = read_chunks();
+        drop(sender);
+
+        let counts = workers
+            .into_iter()
+            .map(|w| w.join().expect("A worker panicked"))
+            .collect::<io::Result<Vec<PasswordCount>>>();
+        (read_result, counts)
+    });
+    read_result?;
+
+    let mut total = PasswordCount::default();
+    for count in counts? {
+        total.valid += count.valid;
+        total.skipped.extend(count.skipped);
+    }
+    total.skipped.sort_by_key(|e| e.line);
+    match (error_mode, total.skipped.first()) {
+        (ErrorMode::Abort, Some(first)) => Err(CountError::Line(*first)),
+        _ => Ok(total),
+    }
+}
+
+pub fn count_valid_passwords_in_file<P>(
+    file_name: &str,
+    password_policy: &P,
+    error_mode: ErrorMode,
+    threads: usize,
+) -> Result<PasswordCount, CountError>
+where
+    P: PasswordPolicy + Sync + ?Sized,
+{
+    let input = File::open(file_name)?;
+    count_valid_passwords_parallel(BufReader::new(input), password_policy, error_mode, threads)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{
+        day_2::{number_of_valid_passwords, PolicyStrategy},
+        random::Lcg,
+    };
+    use std::io::Cursor;
+
+    // Builds a repeatable mix of valid, invalid and unparseable lines.
+    fn generated_input(lines: usize) -> Vec<String> {
+        let mut rng = Lcg::new(0x2020);
+        (0..lines)
+            .map(|idx| {
+                let min = rng.below(5) + 1;
+                let max = min + rng.below(5);
+                let target = rng.pick("abcd");
+                let password = rng.string(12, "abcd");
+                if idx % 997 == 0 {
+                    format!("{}-x {}: {}", min, target, password)
+                } else {
+                    format!("{}-{} {}: {}", min, max, target, password)
+                }
+            })
+            .collect()
+    }
+
+    fn as_reader(lines: &[String]) -> Cursor<Vec<u8>> {
+        Cursor::new(lines.join("\n").into_bytes())
+    }
+
+    #[test]
+    fn test_count_matches_serial() {
+        let input = generated_input(5000);
+        for strategy in [PolicyStrategy::MinMax, PolicyStrategy::Position] {
+            let serial = number_of_valid_passwords(&input, &strategy, ErrorMode::Skip)
+                .expect("Skipping never aborts");
+            for threads in 1..=4 {
+                for chunk_bytes in [1, 7, 1000, 1 << 20] {
+                    let parallel = count_in_chunks(
+                        as_reader(&input),
+                        &strategy,
+                        ErrorMode::Skip,
+                        threads,
+                        chunk_bytes,
+                    )
+                    .expect("Skipping never aborts");
+                    assert_eq!(parallel, serial);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_abort_reports_first_bad_line() {
+        let input = generated_input(5000);
+        let serial = number_of_valid_passwords(&input, &PolicyStrategy::MinMax, ErrorMode::Abort)
+            .expect_err("Input has bad lines");
+        for chunk_bytes in [1, 500, 1 << 20] {
+            match count_in_chunks(
+                as_reader(&input),
+                &PolicyStrategy::MinMax,
+                ErrorMode::Abort,
+                4,
+                chunk_bytes,
+            ) {
+                Err(CountError::Line(error)) => assert_eq!(error, serial),
+                other => panic!("Expected a line error, got {:?}", other),
+            }
+        }
+    }
+
+    #[test]
+    fn test_windows_line_endings_and_default_threads() {
+        let input = Cursor::new("1-3 a: abcde\r\n1-3 b: cdefg\r\n2-9 c: ccccccccc\r\n");
+        let count =
+            count_valid_passwords_parallel(input, &PolicyStrategy::MinMax, ErrorMode::Abort, 0)
+                .expect("Input is valid");
+        assert_eq!(count.valid, 2)
+    }
+
+    #[test]
+    fn test_invalid_utf8_is_an_io_error() {
+        let input = Cursor::new(b"1-3 a: abcde\n1-3 b: \xFFcdefg\n".to_vec());
+        match count_valid_passwords_parallel(input, &PolicyStrategy::MinMax, ErrorMode::Skip, 2) {
+            Err(CountError::Io(e)) => assert_eq!(e.kind(), io::ErrorKind::InvalidData),
+            other => panic!("Expected an I/O error, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_count_valid_passwords_in_file() {
+        let count = count_valid_passwords_in_file(
+            "day_2_test.txt",
+            &PolicyStrategy::MinMax,
+            ErrorMode::Abort,
+            2,
+        )
+        .expect("Unable to count the file");
+        let serial = number_of_valid_passwords(
+            &crate::day_2::load_input_file("day_2_test.txt").expect("Unable to load the file"),
+            &PolicyStrategy::MinMax,
+            ErrorMode::Abort,
+        )
+        .expect("Input is valid");
+        assert_eq!(count, serial)
+    }
+
+    // Spans several of the default chunks, so the real chunk size is
+    // exercised across thread counts.
+    #[test]
+    fn test_large_input_matches_serial() {
+        let input = generated_input(200_000);
+        let bytes = input.join("\n").into_bytes();
+        assert!(bytes.len() > 3 * CHUNK_BYTES);
+        let serial = number_of_valid_passwords(&input, &PolicyStrategy::MinMax, ErrorMode::Skip)
+            .expect("Skipping never aborts");
+        for threads in [1, 2, 4, 8] {
+            let parallel = count_valid_passwords_parallel(
+                Cursor::new(&bytes),
+                &PolicyStrategy::MinMax,
+                ErrorMode::Skip,
+                threads,
+            )
+            .expect("Skipping never aborts");
+            assert_eq!(parallel, serial);
+        }
+    }
+}
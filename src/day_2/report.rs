@@ -0,0 +1,198 @@
+use super::{split_input_string, ParseError, PasswordPolicy, Policy, PolicyStrategy};
+use std::io::{self, Write};
+
+// Ok when the password passes a strategy, otherwise the reason it failed.
+pub type Verdict = Result<(), String>;
+
+fn verdict(strategy: PolicyStrategy, policy: &Policy, password: &str) -> Verdict {
+    match strategy.failure_reason(policy, password) {
+        Some(reason) => Err(reason),
+        None => Ok(()),
+    }
+}
+
+// A line that parsed, with its verdict under each of the puzzle strategies.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct ParsedLine {
+    pub policy: Policy,
+    pub password: String,
+    pub min_max: Verdict,
+    pub position: Verdict,
+}
+
+// What happened to one line of input. Lines count from 1.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct LineReport {
+    pub line: usize,
+    pub outcome: Result<ParsedLine, ParseError>,
+}
+
+impl LineReport {
+    // True when the line parsed and both strategies reached the same verdict.
+    pub fn strategies_agree(&self) -> bool {
+        match &self.outcome {
+            Ok(parsed) => parsed.min_max.is_ok() == parsed.position.is_ok(),
+            Err(_) => false,
+        }
+    }
+}
+
+pub fn validation_report(input: &[String]) -> Vec<LineReport> {
+    input
+        .iter()
+        .enumerate()
+        .map(|(idx, line)| LineReport {
+            line: idx + 1,
+            outcome: split_input_string(line).map(|(policy, password)| ParsedLine {
+                min_max: verdict(PolicyStrategy::MinMax, &policy, &password),
+                position: verdict(PolicyStrategy::Position, &policy, &password),
+                policy,
+                password,
+            }),
+        })
+        .collect()
+}
+
+fn verdict_fields(verdict: &Verdict) -> [String; 2] {
+    match verdict {
+        Ok(()) => ["valid".to_string(), String::new()],
+        Err(reason) => ["invalid".to_string(), reason.clone()],
+    }
+}
+
+pub fn write_report_csv<W: Write>(report: &[LineReport], writer: W) -> io::Result<()> {
+    let mut csv = csv::Writer::from_writer(writer);
+    csv.write_record([
+        "line",
+        "min",
+        "max",
+        "char",
+        "password",
+        "min_max",
+        "min_max_reason",
+        "position",
+        "position_reason",
+        "parse_error",
+    ])?;
+    for line_report in report {
+        let fields = match &line_report.outcome {
+            Ok(parsed) => {
+                let [min_max, min_max_reason] = verdict_fields(&parsed.min_max);
+                let [position, position_reason] = verdict_fields(&parsed.position);
+                [
+                    line_report.line.to_string(),
+                    parsed.policy.min.to_string(),
+                    parsed.policy.max.to_string(),
+                    parsed.policy.target_char.to_string(),
+                    parsed.password.clone(),
+                    min_max,
+                    min_max_reason,
+                    position,
+                    position_reason,
+                    String::new(),
+                ]
+            }
+            Err(error) => {
+                let mut fields: [String; 10] = Default::default();
+                fields[0] = line_report.line.to_string();
+                fields[9] = error.to_string();
+                fields
+            }
+        };
+        csv.write_record(&fields)?;
+    }
+    csv.flush()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::day_2::ParseReason;
+
+    fn example_input() -> Vec<String> {
+        Vec::from([
+            "1-3 a: abcde".to_string(),
+            "1-3 b: cdefg".to_string(),
+            "2-9 c: ccccccccc".to_string(),
+            "3-a b: xyz".to_string(),
+        ])
+    }
+
+    #[test]
+    fn test_validation_report() {
+        let report = validation_report(&example_input());
+        assert_eq!(
+            report[0].outcome,
+            Ok(ParsedLine {
+                policy: Policy::new(1, 3, 'a'),
+                password: "abcde".to_string(),
+                min_max: Ok(()),
+                position: Ok(()),
+            })
+        );
+        assert_eq!(
+            report[1].outcome.as_ref().map(|p| p.min_max.clone()),
+            Ok(Err("found 0 'b', allowed 1\u{2013}3".to_string()))
+        );
+        assert_eq!(
+            report[1].outcome.as_ref().map(|p| p.position.clone()),
+            Ok(Err(
+                "position 1 is 'c' and position 3 is 'e', expected exactly one 'b'".to_string()
+            ))
+        );
+        assert_eq!(
+            report[3],
+            LineReport {
+                line: 4,
+                outcome: Err(ParseError {
+                    column: 3,
+                    reason: ParseReason::ExpectedNumber,
+                }),
+            }
+        );
+        let agreeing = report
+            .iter()
+            .map(LineReport::strategies_agree)
+            .collect::<Vec<bool>>();
+        assert_eq!(agreeing, [true, true, false, false])
+    }
+
+    #[test]
+    fn test_position_failure_out_of_range() {
+        let input = Vec::from(["2-12 z: za".to_string()]);
+        let report = validation_report(&input);
+        assert_eq!(
+            report[0].outcome.as_ref().map(|p| p.position.clone()),
+            Ok(Err(
+                "position 2 is 'a' and position 12 is out of range, expected exactly one 'z'"
+                    .to_string()
+            ))
+        );
+    }
+
+    #[test]
+    fn test_write_report_csv() {
+        let mut input = example_input();
+        input.push("1-1 x: a,\"b\"".to_string());
+        let mut output = Vec::new();
+        write_report_csv(&validation_report(&input), &mut output).expect("Unable to write CSV");
+        let csv = String::from_utf8(output.clone()).expect("CSV should be UTF-8");
+        let lines = csv.lines().collect::<Vec<&str>>();
+        assert_eq!(
+            lines,
+            [
+                "line,min,max,char,password,min_max,min_max_reason,position,position_reason,parse_error",
+                "1,1,3,a,abcde,valid,,valid,,",
+                "2,1,3,b,cdefg,invalid,\"found 0 'b', allowed 1\u{2013}3\",invalid,\"position 1 is 'c' and position 3 is 'e', expected exactly one 'b'\",",
+                "3,2,9,c,ccccccccc,valid,,invalid,\"position 2 is 'c' and position 9 is 'c', expected exactly one 'c'\",",
+                "4,,,,,,,,,column 3: expected a number",
+                "5,1,1,x,\"a,\"\"b\"\"\",invalid,\"found 0 'x', allowed 1\u{2013}1\",invalid,\"position 1 is 'a' and position 1 is 'a', expected exactly one 'x'\",",
+            ]
+        );
+        let passwords = csv::Reader::from_reader(output.as_slice())
+            .records()
+            .map(|row| row.expect("CSV should parse")[4].to_string())
+            .collect::<Vec<String>>();
+        assert_eq!(passwords, ["abcde", "cdefg", "ccccccccc", "", "a,\"b\""]);
+    }
+}
@@ -0,0 +1,447 @@
+use super::{min_max_strategy, position_matches, PasswordPolicy, Policy, PositionMode};
+use std::{error::Error, fmt, ops::Not, str::FromStr};
+
+// A compound password rule built from the two puzzle primitives. Rules can
+// be combined with the and, or and xor methods and the ! operator, or parsed
+// from text such as "count(a) in 1..3 AND (pos(1)=b XOR pos(5)=b)".
+//
+// In the text form NOT binds tightest, then AND, then XOR, then OR. Counts
+// are inclusive and positions count from 1. A character that is not a letter
+// or digit can be written in single quotes, as in count('.') in 0..0.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum Rule {
+    Count {
+        target_char: char,
+        min: u32,
+        max: u32,
+    },
+    Position {
+        position: u32,
+        target_char: char,
+    },
+    Not(Box<Rule>),
+    And(Box<Rule>, Box<Rule>),
+    Or(Box<Rule>, Box<Rule>),
+    Xor(Box<Rule>, Box<Rule>),
+}
+
+impl Rule {
+    pub fn count(target_char: char, min: u32, max: u32) -> Rule {
+        Rule::Count {
+            target_char,
+            min,
+            max,
+        }
+    }
+
+    pub fn position(position: u32, target_char: char) -> Rule {
+        Rule::Position {
+            position,
+            target_char,
+        }
+    }
+
+    pub fn and(self, other: Rule) -> Rule {
+        Rule::And(Box::new(self), Box::new(other))
+    }
+
+    pub fn or(self, other: Rule) -> Rule {
+        Rule::Or(Box::new(self), Box::new(other))
+    }
+
+    pub fn xor(self, other: Rule) -> Rule {
+        Rule::Xor(Box::new(self), Box::new(other))
+    }
+
+    pub fn evaluate(&self, password: &str) -> bool {
+        match self {
+            Rule::Count {
+                target_char,
+                min,
+                max,
+            } => min_max_strategy(&Policy::new(*min, *max, *target_char), password),
+            Rule::Position {
+                position,
+                target_char,
+            } => position_matches(password, *position, *target_char, PositionMode::Char),
+            Rule::Not(rule) => !rule.evaluate(password),
+            Rule::And(left, right) => left.evaluate(password) && right.evaluate(password),
+            Rule::Or(left, right) => left.evaluate(password) || right.evaluate(password),
+            Rule::Xor(left, right) => left.evaluate(password) != right.evaluate(password),
+        }
+    }
+}
+
+impl Not for Rule {
+    type Output = Rule;
+
+    fn not(self) -> Rule {
+        Rule::Not(Box::new(self))
+    }
+}
+
+// A rule ignores the policy on the line and applies only to the password.
+impl PasswordPolicy for Rule {
+    fn is_valid(&self, _policy: &Policy, password: &str) -> bool {
+        self.evaluate(password)
+    }
+}
+
+struct RuleChar(char);
+
+impl fmt::Display for RuleChar {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if self.0.is_alphanumeric() {
+            write!(f, "{}", self.0)
+        } else {
+            write!(f, "'{}'", self.0)
+        }
+    }
+}
+
+// Writes the rule back out in the text form, bracketing every compound
+// sub-rule so that it always parses back to the same tree.
+impl fmt::Display for Rule {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let operand = |rule: &Rule| match rule {
+            Rule::Count { .. } | Rule::Position { .. } | Rule::Not(_) => rule.to_string(),
+            _ => format!("({})", rule),
+        };
+        match self {
+            Rule::Count {
+                target_char,
+                min,
+                max,
+            } => write!(f, "count({}) in {}..{}", RuleChar(*target_char), min, max),
+            Rule::Position {
+                position,
+                target_char,
+            } => write!(f, "pos({})={}", position, RuleChar(*target_char)),
+            Rule::Not(rule) => write!(f, "NOT {}", operand(rule)),
+            Rule::And(left, right) => write!(f, "{} AND {}", operand(left), operand(right)),
+            Rule::Or(left, right) => write!(f, "{} OR {}", operand(left), operand(right)),
+            Rule::Xor(left, right) => write!(f, "{} XOR {}", operand(left), operand(right)),
+        }
+    }
+}
+
+// Where and why a rule could not be parsed. Columns count characters from 1.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct RuleParseError {
+    pub column: usize,
+    pub expected: &'static str,
+}
+
+impl fmt::Display for RuleParseError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "column {}: expected {}", self.column, self.expected)
+    }
+}
+
+impl Error for RuleParseError {}
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+enum Token {
+    OpenBracket,
+    CloseBracket,
+    Equals,
+    Range,
+    Number(u32),
+    Quoted(char),
+    Word(String),
+}
+
+fn is_word_char(c: char) -> bool {
+    !c.is_whitespace() && !"()=.'".contains(c) && !c.is_ascii_digit()
+}
+
+fn tokenise(input: &str) -> Result<Vec<(usize, Token)>, RuleParseError> {
+    let chars = input.chars().collect::<Vec<char>>();
+    let mut tokens = Vec::new();
+    let mut idx = 0;
+    while idx < chars.len() {
+        let column = idx + 1;
+        let error = |expected| RuleParseError { column, expected };
+        let token = match chars[idx] {
+            c if c.is_whitespace() => {
+                idx += 1;
+                continue;
+            }
+            '(' => Token::OpenBracket,
+            ')' => Token::CloseBracket,
+            '=' => Token::Equals,
+            '.' if chars.get(idx + 1) == Some(&'.') => {
+                idx += 1;
+                Token::Range
+            }
+            '\'' => match (chars.get(idx + 1), chars.get(idx + 2)) {
+                (Some(c), Some('\'')) => {
+                    idx += 2;
+                    Token::Quoted(*c)
+                }
+                _ => return Err(error("a quoted character")),
+            },
+            c if c.is_ascii_digit() => {
+                let start = idx;
+                while chars.get(idx + 1).is_some_and(|c| c.is_ascii_digit()) {
+                    idx += 1;
+                }
+                let number = chars[start..=idx].iter().collect::<String>();
+                Token::Number(number.parse().map_err(|_| error("a smaller number"))?)
+            }
+            c if is_word_char(c) => {
+                let start = idx;
+                while chars.get(idx + 1).is_some_and(|c| is_word_char(*c)) {
+                    idx += 1;
+                }
+                Token::Word(chars[start..=idx].iter().collect())
+            }
+            _ => return Err(error("a rule")),
+        };
+        tokens.push((column, token));
+        idx += 1;
+    }
+    Ok(tokens)
+}
+
+struct RuleParser {
+    tokens: Vec<(usize, Token)>,
+    position: usize,
+    end_column: usize,
+}
+
+impl RuleParser {
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.position).map(|(_, token)| token)
+    }
+
+    fn error(&self, expected: &'static str) -> RuleParseError {
+        let column = match self.tokens.get(self.position) {
+            Some((column, _)) => *column,
+            None => self.end_column,
+        };
+        RuleParseError { column, expected }
+    }
+
+    fn next(&mut self) -> Option<Token> {
+        let token = self.peek().cloned();
+        self.position += 1;
+        token
+    }
+
+    fn keyword(&mut self, keyword: &str) -> bool {
+        match self.peek() {
+            Some(Token::Word(word)) if word.eq_ignore_ascii_case(keyword) => {
+                self.position += 1;
+                true
+            }
+            _ => false,
+        }
+    }
+
+    fn expect(&mut self, token: Token, expected: &'static str) -> Result<(), RuleParseError> {
+        if self.peek() == Some(&token) {
+            self.position += 1;
+            Ok(())
+        } else {
+            Err(self.error(expected))
+        }
+    }
+
+    fn number(&mut self) -> Result<u32, RuleParseError> {
+        match self.peek() {
+            Some(Token::Number(n)) => {
+                let n = *n;
+                self.position += 1;
+                Ok(n)
+            }
+            _ => Err(self.error("a number")),
+        }
+    }
+
+    fn character(&mut self) -> Result<char, RuleParseError> {
+        let error = self.error("a single character");
+        let c = match self.next() {
+            Some(Token::Quoted(c)) => Some(c),
+            Some(Token::Word(word)) => single_char(&word),
+            Some(Token::Number(n)) if n < 10 => std::char::from_digit(n, 10),
+            _ => None,
+        };
+        c.ok_or(error)
+    }
+
+    fn or(&mut self) -> Result<Rule, RuleParseError> {
+        let mut rule = self.xor()?;
+        while self.keyword("OR") {
+            rule = rule.or(self.xor()?);
+        }
+        Ok(rule)
+    }
+
+    fn xor(&mut self) -> Result<Rule, RuleParseError> {
+        let mut rule = self.and()?;
+        while self.keyword("XOR") {
+            rule = rule.xor(self.and()?);
+        }
+        Ok(rule)
+    }
+
+    fn and(&mut self) -> Result<Rule, RuleParseError> {
+        let mut rule = self.unary()?;
+        while self.keyword("AND") {
+            rule = rule.and(self.unary()?);
+        }
+        Ok(rule)
+    }
+
+    fn unary(&mut self) -> Result<Rule, RuleParseError> {
+        if self.keyword("NOT") {
+            Ok(!self.unary()?)
+        } else {
+            self.atom()
+        }
+    }
+
+    fn atom(&mut self) -> Result<Rule, RuleParseError> {
+        if self.peek() == Some(&Token::OpenBracket) {
+            self.position += 1;
+            let rule = self.or()?;
+            self.expect(Token::CloseBracket, "')'")?;
+            Ok(rule)
+        } else if self.keyword("count") {
+            self.expect(Token::OpenBracket, "'('")?;
+            let target_char = self.character()?;
+            self.expect(Token::CloseBracket, "')'")?;
+            if !self.keyword("in") {
+                return Err(self.error("'in'"));
+            }
+            let min = self.number()?;
+            self.expect(Token::Range, "'..'")?;
+            let max = self.number()?;
+            Ok(Rule::count(target_char, min, max))
+        } else if self.keyword("pos") {
+            self.expect(Token::OpenBracket, "'('")?;
+            let position = self.number()?;
+            self.expect(Token::CloseBracket, "')'")?;
+            self.expect(Token::Equals, "'='")?;
+            let target_char = self.character()?;
+            Ok(Rule::position(position, target_char))
+        } else {
+            Err(self.error("count, pos, NOT or '('"))
+        }
+    }
+}
+
+fn single_char(word: &str) -> Option<char> {
+    let mut chars = word.chars();
+    match (chars.next(), chars.next()) {
+        (Some(c), None) => Some(c),
+        _ => None,
+    }
+}
+
+impl FromStr for Rule {
+    type Err = RuleParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut parser = RuleParser {
+            tokens: tokenise(s)?,
+            position: 0,
+            end_column: s.chars().count() + 1,
+        };
+        let rule = parser.or()?;
+        match parser.peek() {
+            Some(_) => Err(parser.error("AND, OR, XOR or the end of the rule")),
+            None => Ok(rule),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::day_2::{number_of_valid_passwords, ErrorMode};
+
+    #[test]
+    fn test_parse_rule() {
+        let rule = "count(a) in 1..3 AND (pos(1)=b XOR pos(5)=b)".parse::<Rule>();
+        let correct_rule =
+            Rule::count('a', 1, 3).and(Rule::position(1, 'b').xor(Rule::position(5, 'b')));
+        assert_eq!(rule, Ok(correct_rule))
+    }
+
+    #[test]
+    fn test_parse_rule_precedence() {
+        let rule = "NOT pos(1)=a AND pos(2)=b XOR pos(3)=c OR pos(4)=d".parse::<Rule>();
+        let correct_rule = (!Rule::position(1, 'a'))
+            .and(Rule::position(2, 'b'))
+            .xor(Rule::position(3, 'c'))
+            .or(Rule::position(4, 'd'));
+        assert_eq!(rule, Ok(correct_rule))
+    }
+
+    #[test]
+    fn test_parse_rule_quoted_and_digit_characters() {
+        let rule = "count('.') in 0..0 and pos(2)=7".parse::<Rule>();
+        let correct_rule = Rule::count('.', 0, 0).and(Rule::position(2, '7'));
+        assert_eq!(rule, Ok(correct_rule))
+    }
+
+    #[test]
+    fn test_parse_rule_errors() {
+        let error = |column, expected| Err(RuleParseError { column, expected });
+        assert_eq!(
+            "count(ab) in 1..3".parse::<Rule>(),
+            error(7, "a single character")
+        );
+        assert_eq!("count(a) 1..3".parse::<Rule>(), error(10, "'in'"));
+        assert_eq!(
+            "pos(1)=a AND".parse::<Rule>(),
+            error(13, "count, pos, NOT or '('")
+        );
+        assert_eq!("(pos(1)=a".parse::<Rule>(), error(10, "')'"));
+        assert_eq!(
+            "pos(1)=a pos(2)=b".parse::<Rule>(),
+            error(10, "AND, OR, XOR or the end of the rule")
+        );
+        assert_eq!("pos(1)='a".parse::<Rule>(), error(8, "a quoted character"));
+    }
+
+    #[test]
+    fn test_display_round_trip() {
+        let not_a_or_hash = !Rule::count('a', 1, 3).or(Rule::position(2, '#'));
+        let rule = not_a_or_hash.and(Rule::position(1, 'b').xor(Rule::count('c', 0, 2)));
+        let text = rule.to_string();
+        assert_eq!(
+            text,
+            "NOT (count(a) in 1..3 OR pos(2)='#') AND (pos(1)=b XOR count(c) in 0..2)"
+        );
+        assert_eq!(text.parse::<Rule>(), Ok(rule))
+    }
+
+    #[test]
+    fn test_evaluate() {
+        let rule = "count(a) in 1..3 AND (pos(1)=b XOR pos(5)=b)"
+            .parse::<Rule>()
+            .expect("Unable to parse rule");
+        assert!(rule.evaluate("bacde"));
+        assert!(rule.evaluate("aacdb"));
+        assert!(!rule.evaluate("bacdb"));
+        assert!(!rule.evaluate("bcdef"));
+        assert!(!rule.evaluate("baaaa"))
+    }
+
+    #[test]
+    fn test_rule_as_password_policy() {
+        let input = Vec::from([
+            "1-3 a: abcde".to_string(),
+            "1-3 b: cdefg".to_string(),
+            "2-9 c: ccccccccc".to_string(),
+        ]);
+        let rule = "NOT count(c) in 2..9"
+            .parse::<Rule>()
+            .expect("Unable to parse rule");
+        let count = number_of_valid_passwords(&input, &rule, ErrorMode::Abort);
+        assert_eq!(Ok(2), count.map(|c| c.valid))
+    }
+}
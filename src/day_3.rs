@@ -1,65 +1,266 @@
+use crate::{
+    answer::{Answer, OverflowError, Precision},
+    grid::{EdgePolicy, Edges, Grid, GridError},
+};
 use std::{
+    fmt,
     fs::File,
     io,
     io::{BufRead, BufReader},
+    ops::RangeInclusive,
 };
 
-#[derive(Debug, Eq, PartialEq)]
+mod render;
+mod terrain;
+
+pub use render::{render_ascii, render_svg, TooLargeError};
+pub use terrain::{Effect, Legend, Ride, Terrain, TerrainMap};
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
 enum Square {
     Open,
     Tree,
 }
 
-fn create_grid_line(input: &str) -> Vec<Square> {
-    input
-        .chars()
-        .map(|c| if c == '.' { Square::Open } else { Square::Tree })
-        .collect()
+impl fmt::Display for Square {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Square::Open => write!(f, "."),
+            Square::Tree => write!(f, "#"),
+        }
+    }
+}
+
+// The map repeats to the right but stops at the bottom.
+const MAP_EDGES: Edges = Edges {
+    horizontal: EdgePolicy::Wrap,
+    vertical: EdgePolicy::Bounded,
+};
+
+fn create_grid(input: &[String]) -> Result<Grid<Square>, GridError> {
+    Grid::from_chars(input, |c| match c {
+        '.' => Some(Square::Open),
+        '#' => Some(Square::Tree),
+        _ => None,
+    })
+}
+
+// Squares moved right and down on each step. Negative values go left or up.
+pub type Slope = (isize, isize);
+
+type Position = (isize, isize);
+
+// How the toboggan crosses the map. The journey ends when a step leaves the
+// map over a bounded edge. Without a step limit it also ends as soon as the
+// toboggan would land on a square it has already visited, since from there it
+// can only go round the same loop again; with a step limit it keeps going,
+// loops included, until it has taken that many steps.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct Journey {
+    pub slope: Slope,
+    pub edges: Edges,
+    pub step_limit: Option<usize>,
 }
 
-fn create_grid(input: &[String]) -> Vec<Vec<Square>> {
-    input.iter().map(|line| create_grid_line(line)).collect()
+impl Journey {
+    // The puzzle's journey: the map repeats to the right and ends at the bottom.
+    pub fn new(slope: Slope) -> Journey {
+        Journey {
+            slope,
+            edges: MAP_EDGES,
+            step_limit: None,
+        }
+    }
 }
 
-fn move_tobogan(start_position: (usize, usize), slope: (usize, usize)) -> (usize, usize) {
-    (start_position.0 + slope.0, start_position.1 + slope.1)
+// Where one step lands, or None if it is too far from the start to
+// represent, which ends the journey.
+fn move_tobogan(start_position: Position, slope: Slope) -> Option<Position> {
+    Some((
+        start_position.0.checked_add(slope.0)?,
+        start_position.1.checked_add(slope.1)?,
+    ))
 }
 
-fn positions_used_to_reach_bottom(
-    grid: &[Vec<Square>],
-    slope: (usize, usize),
-) -> Vec<(usize, usize)> {
-    let mut start_position = (0, 0);
-    let grid_depth = grid.len() - 1;
-    let mut positions = Vec::new();
-    loop {
-        let end_position = move_tobogan(start_position, slope);
-        if end_position.1 == grid_depth {
-            positions.push(end_position);
-            return positions;
-        } else {
-            positions.push(end_position);
-            start_position = end_position
+fn gcd(a: usize, b: usize) -> usize {
+    if b == 0 {
+        a
+    } else {
+        gcd(b, a % b)
+    }
+}
+
+// How many squares a journey from (0, 0) lands on before it would reach one it
+// has already visited, or None if it only stops by leaving the map. Each axis
+// is independent: a wrapping axis comes back to 0 after a fixed period, a
+// clamped axis stops changing once it reaches the edge it is heading for, and
+// a bounded axis that moves never repeats. The first repeat comes one full
+// wrapping period after every clamped axis has settled.
+fn steps_before_repeat(width: usize, height: usize, journey: &Journey) -> Option<usize> {
+    let (dx, dy) = journey.slope;
+    let axes = [
+        (dx, width, journey.edges.horizontal),
+        (dy, height, journey.edges.vertical),
+    ];
+    let mut settled_after = 0;
+    let mut period = 1;
+    for (step, length, policy) in axes {
+        if step == 0 || length == 0 {
+            continue;
+        }
+        match policy {
+            EdgePolicy::Bounded => return None,
+            EdgePolicy::Wrap => {
+                let axis_period = length / gcd(step.unsigned_abs() % length, length);
+                period = (period / gcd(period, axis_period)).saturating_mul(axis_period);
+            }
+            EdgePolicy::Clamp if step > 0 => {
+                settled_after = settled_after.max((length - 1).div_ceil(step as usize));
+            }
+            EdgePolicy::Clamp => (),
+        }
+    }
+    Some(settled_after.saturating_add(period) - 1)
+}
+
+// The map with each row packed into a bitset of tree positions. Parse it once
+// and reuse it for as many journeys as needed.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Forest {
+    width: usize,
+    height: usize,
+    row_words: usize,
+    trees: Vec<u64>,
+}
+
+impl Forest {
+    pub fn parse(input: &[String]) -> Result<Forest, GridError> {
+        let grid = create_grid(input)?;
+        let row_words = grid.width().div_ceil(64);
+        let mut trees = vec![0; row_words * grid.height()];
+        for ((x, y), square) in grid.iter() {
+            if *square == Square::Tree {
+                trees[y * row_words + x / 64] |= 1 << (x % 64);
+            }
+        }
+        Ok(Forest {
+            width: grid.width(),
+            height: grid.height(),
+            row_words,
+            trees,
+        })
+    }
+
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    pub fn height(&self) -> usize {
+        self.height
+    }
+
+    // False for positions outside the map.
+    pub fn is_tree(&self, x: usize, y: usize) -> bool {
+        x < self.width
+            && y < self.height
+            && self.trees[y * self.row_words + x / 64] & (1 << (x % 64)) != 0
+    }
+
+    // The squares a journey lands on, in order, each paired with whether a
+    // tree stands there. Positions are not wrapped, so they keep counting past
+    // the edge of a repeating map.
+    pub fn visited(&self, journey: &Journey) -> Visited<'_> {
+        Visited {
+            forest: self,
+            slope: journey.slope,
+            edges: journey.edges,
+            position: (0, 0),
+            remaining: journey
+                .step_limit
+                .or_else(|| steps_before_repeat(self.width, self.height, journey)),
+        }
+    }
+
+    pub fn count_trees(&self, slope: Slope) -> u64 {
+        self.count_trees_on_journey(&Journey::new(slope))
+    }
+
+    pub fn count_trees_on_journey(&self, journey: &Journey) -> u64 {
+        self.visited(journey).filter(|(_, tree)| *tree).count() as u64
+    }
+
+    // Multiplies the tree counts, failing rather than wrapping if the product
+    // grows wider than the limit.
+    pub fn tree_product(
+        &self,
+        slopes: &[Slope],
+        limit: Precision,
+    ) -> Result<Answer, OverflowError> {
+        Answer::product(slopes.iter().map(|s| self.count_trees(*s)), limit)
+    }
+
+    // Tries every slope within the bounds on the puzzle's journey and ranks
+    // them, ties going to the earlier slope. Slopes that never land on a
+    // square are left out, so the first entry is the best real route.
+    pub fn rank_slopes(
+        &self,
+        dx: RangeInclusive<isize>,
+        dy: RangeInclusive<isize>,
+        ranking: Ranking,
+    ) -> Vec<SlopeTrees> {
+        let mut table = dy
+            .flat_map(|dy| dx.clone().map(move |dx| (dx, dy)))
+            .map(|slope| {
+                let (squares, trees) = self
+                    .visited(&Journey::new(slope))
+                    .fold((0, 0), |(squares, trees), (_, tree)| {
+                        (squares + 1, trees + tree as u64)
+                    });
+                SlopeTrees {
+                    slope,
+                    squares,
+                    trees,
+                }
+            })
+            .filter(|result| result.squares > 0)
+            .collect::<Vec<SlopeTrees>>();
+        match ranking {
+            Ranking::FewestTrees => table.sort_by_key(|result| result.trees),
+            Ranking::MostTrees => table.sort_by_key(|result| std::cmp::Reverse(result.trees)),
         }
+        table
     }
 }
 
-fn get_square(grid: &[Vec<Square>], position: (usize, usize)) -> Option<&Square> {
-    let (x, y) = position;
-    let grid_line = match grid.get(y) {
-        Some(grid_line) => grid_line,
-        None => return None,
-    };
-    grid_line.get(x % grid_line.len())
+pub struct Visited<'a> {
+    forest: &'a Forest,
+    slope: Slope,
+    edges: Edges,
+    position: Position,
+    remaining: Option<usize>,
 }
 
-pub fn count_trees(input: &[String], slope: (usize, usize)) -> u64 {
-    let grid = create_grid(input);
-    let positions = positions_used_to_reach_bottom(&grid, slope);
-    positions
-        .iter()
-        .filter(|p| get_square(&grid, **p) == Some(&Square::Tree))
-        .count() as u64
+impl Iterator for Visited<'_> {
+    type Item = (Position, bool);
+
+    fn next(&mut self) -> Option<(Position, bool)> {
+        if self.remaining == Some(0) {
+            return None;
+        }
+        self.position = move_tobogan(self.position, self.slope)?;
+        let x = self
+            .edges
+            .horizontal
+            .resolve(self.position.0, self.forest.width)?;
+        let y = self
+            .edges
+            .vertical
+            .resolve(self.position.1, self.forest.height)?;
+        if let Some(remaining) = &mut self.remaining {
+            *remaining -= 1;
+        }
+        Some((self.position, self.forest.is_tree(x, y)))
+    }
 }
 
 pub fn load_input_file(file_name: &str) -> io::Result<Vec<String>> {
@@ -73,65 +274,79 @@ pub fn load_input_file(file_name: &str) -> io::Result<Vec<String>> {
     Ok(lines)
 }
 
-pub fn tree_product(input: &[String], slopes: &[(usize, usize)]) -> u64 {
-    slopes.iter().map(|s| count_trees(input, *s)).product()
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Ranking {
+    FewestTrees,
+    MostTrees,
+}
+
+// One row of a slope search: how many squares the toboggan lands on and how
+// many of them hold trees.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct SlopeTrees {
+    pub slope: Slope,
+    pub squares: u64,
+    pub trees: u64,
 }
 
 #[cfg(test)]
 mod test {
     use super::*;
+    use std::collections::HashSet;
 
-    #[test]
-    fn test_create_grid_line() {
-        let input = "..##.......";
-        let correct_grid_line = Vec::from([
-            Square::Open,
-            Square::Open,
-            Square::Tree,
-            Square::Tree,
-            Square::Open,
-            Square::Open,
-            Square::Open,
-            Square::Open,
-            Square::Open,
-            Square::Open,
-            Square::Open,
-        ]);
-        assert_eq!(create_grid_line(input), correct_grid_line);
+    fn positions(forest: &Forest, journey: &Journey) -> Vec<Position> {
+        forest.visited(journey).map(|(p, _)| p).collect()
     }
 
     #[test]
     fn test_create_grid() {
-        let input = Vec::from(["..##.......".to_string(), "#...#...#..".to_string()]);
-        let correct_grid = Vec::from([
-            [
-                Square::Open,
-                Square::Open,
-                Square::Tree,
-                Square::Tree,
-                Square::Open,
-                Square::Open,
-                Square::Open,
-                Square::Open,
-                Square::Open,
-                Square::Open,
-                Square::Open,
-            ],
-            [
-                Square::Tree,
-                Square::Open,
-                Square::Open,
-                Square::Open,
-                Square::Tree,
-                Square::Open,
-                Square::Open,
-                Square::Open,
-                Square::Tree,
-                Square::Open,
-                Square::Open,
-            ],
-        ]);
-        assert_eq!(create_grid(&input), correct_grid)
+        let grid = create_grid(&example_input()[..2]).expect("Map should parse");
+        assert_eq!((grid.width(), grid.height()), (11, 2));
+        assert_eq!(
+            grid.row(0),
+            Some(
+                &[
+                    Square::Open,
+                    Square::Open,
+                    Square::Tree,
+                    Square::Tree,
+                    Square::Open,
+                    Square::Open,
+                    Square::Open,
+                    Square::Open,
+                    Square::Open,
+                    Square::Open,
+                    Square::Open,
+                ][..]
+            )
+        );
+        assert_eq!(grid.to_string(), "..##.......\n#...#...#..")
+    }
+
+    #[test]
+    fn test_create_grid_rejects_ragged_rows() {
+        let input = Vec::from(["..##.......".to_string(), "#...#".to_string()]);
+        assert_eq!(
+            create_grid(&input),
+            Err(GridError::RaggedRow {
+                row: 2,
+                expected: 11,
+                found: 5
+            })
+        )
+    }
+
+    #[test]
+    fn test_create_grid_rejects_unknown_characters() {
+        let input = Vec::from(["..##.......".to_string(), "#...#..x#..".to_string()]);
+        assert_eq!(
+            create_grid(&input),
+            Err(GridError::UnexpectedCharacter {
+                row: 2,
+                column: 8,
+                found: 'x'
+            })
+        )
     }
 
     #[test]
@@ -139,127 +354,153 @@ mod test {
         let start_position = (0, 0);
         let slope = (3, 1);
         let final_position = (3, 1);
-        assert_eq!(move_tobogan(start_position, slope), final_position)
+        assert_eq!(move_tobogan(start_position, slope), Some(final_position));
+        assert_eq!(move_tobogan((1, 0), (isize::MAX, 1)), None);
+        assert_eq!(move_tobogan((0, -1), (0, isize::MIN)), None);
     }
 
     #[test]
-    fn test_positions_used_to_reach_bottom() {
-        let input = Vec::from([
-            "..##.......".to_string(),
-            "#...#...#..".to_string(),
-            ".#....#..#.".to_string(),
-            "..#.#...#.#".to_string(),
-        ]);
-        let grid = create_grid(&input);
+    fn test_positions_visited() {
+        let forest = Forest::parse(&example_input()[..4]).expect("Map should parse");
         let slope = (3, 1);
 
         let correct_positions = Vec::from([(3, 1), (6, 2), (9, 3)]);
-        assert_eq!(
-            positions_used_to_reach_bottom(&grid, slope),
-            correct_positions
-        )
+        assert_eq!(positions(&forest, &Journey::new(slope)), correct_positions)
     }
 
     #[test]
-    fn test_positions_used_to_reach_bottom_with_wrapping() {
-        let input = Vec::from([
-            "..##.......".to_string(),
-            "#...#...#..".to_string(),
-            ".#....#..#.".to_string(),
-            "..#.#...#.#".to_string(),
-            "..#.#...#.#".to_string(),
-        ]);
-        let grid = create_grid(&input);
+    fn test_positions_visited_with_wrapping() {
+        let forest = Forest::parse(&example_input()[..5]).expect("Map should parse");
         let slope = (3, 1);
 
         let correct_positions = Vec::from([(3, 1), (6, 2), (9, 3), (12, 4)]);
+        assert_eq!(positions(&forest, &Journey::new(slope)), correct_positions)
+    }
+
+    #[test]
+    fn test_positions_visited_with_large_vertical_step() {
+        let forest = Forest::parse(&example_input()[..4]).expect("Map should parse");
         assert_eq!(
-            positions_used_to_reach_bottom(&grid, slope),
-            correct_positions
-        )
+            positions(&forest, &Journey::new((1, 2))),
+            Vec::from([(1, 2)])
+        );
+        assert_eq!(positions(&forest, &Journey::new((0, 5))), Vec::new())
+    }
+
+    #[test]
+    fn test_positions_visited_going_left_and_up() {
+        let forest = Forest::parse(&example_input()[..4]).expect("Map should parse");
+        assert_eq!(
+            positions(&forest, &Journey::new((-3, 1))),
+            Vec::from([(-3, 1), (-6, 2), (-9, 3)])
+        );
+        assert_eq!(positions(&forest, &Journey::new((1, -1))), Vec::new());
+        let journey = Journey {
+            edges: Edges::from(EdgePolicy::Wrap),
+            ..Journey::new((1, -1))
+        };
+        let visited = positions(&forest, &journey);
+        assert_eq!(visited[..3], [(1, -1), (2, -2), (3, -3)]);
+        // The toboggan is back at the start after 11 * 4 steps.
+        assert_eq!(visited.len(), 43)
+    }
+
+    #[test]
+    fn test_positions_visited_horizontally() {
+        let input = Vec::from(["..#".to_string(), "#..".to_string()]);
+        let forest = Forest::parse(&input).expect("Map should parse");
+        assert_eq!(
+            positions(&forest, &Journey::new((1, 0))),
+            Vec::from([(1, 0), (2, 0)])
+        );
+        assert_eq!(positions(&forest, &Journey::new((0, 0))), Vec::new());
+        let journey = Journey {
+            step_limit: Some(5),
+            ..Journey::new((1, 0))
+        };
+        assert_eq!(
+            positions(&forest, &journey),
+            Vec::from([(1, 0), (2, 0), (3, 0), (4, 0), (5, 0)])
+        );
+        assert_eq!(forest.count_trees_on_journey(&journey), 2)
+    }
+
+    #[test]
+    fn test_positions_visited_with_clamped_edges() {
+        let input = Vec::from(["..#".to_string(), "#..".to_string()]);
+        let forest = Forest::parse(&input).expect("Map should parse");
+        let journey = Journey {
+            edges: Edges {
+                horizontal: EdgePolicy::Clamp,
+                vertical: EdgePolicy::Bounded,
+            },
+            ..Journey::new((2, 1))
+        };
+        assert_eq!(positions(&forest, &journey), Vec::from([(2, 1)]));
+        let journey = Journey {
+            edges: Edges::from(EdgePolicy::Clamp),
+            ..Journey::new((1, 1))
+        };
+        assert_eq!(positions(&forest, &journey), Vec::from([(1, 1), (2, 2)]))
     }
 
     #[test]
     fn test_count_trees() {
-        let input = Vec::from([
-            "..##.......".to_string(),
-            "#...#...#..".to_string(),
-            ".#....#..#.".to_string(),
-            "..#.#...#.#".to_string(),
-        ]);
+        let forest = Forest::parse(&example_input()[..4]).expect("Map should parse");
         let slope = (3, 1);
 
-        assert_eq!(count_trees(&input, slope), 1)
+        assert_eq!(forest.count_trees(slope), 1)
+    }
+
+    #[test]
+    fn test_journey_ends_when_the_position_overflows() {
+        let forest = example_forest();
+        // isize::MAX wraps to column 7, and the next step cannot be taken.
+        assert_eq!(forest.count_trees((isize::MAX, 1)), 0);
+        assert_eq!(forest.count_trees((isize::MAX, 8)), 1);
+        let journey = Journey {
+            edges: Edges {
+                horizontal: EdgePolicy::Wrap,
+                vertical: EdgePolicy::Wrap,
+            },
+            step_limit: Some(10),
+            ..Journey::new((isize::MAX, isize::MAX))
+        };
+        assert_eq!(positions(&forest, &journey), [(isize::MAX, isize::MAX)]);
     }
 
     #[test]
     fn test_count_trees_example() {
-        let input = Vec::from([
-            "..##.......".to_string(),
-            "#...#...#..".to_string(),
-            ".#....#..#.".to_string(),
-            "..#.#...#.#".to_string(),
-            ".#...##..#.".to_string(),
-            "..#.##.....".to_string(),
-            ".#.#.#....#".to_string(),
-            ".#........#".to_string(),
-            "#.##...#...".to_string(),
-            "#...##....#".to_string(),
-            ".#..#...#.#".to_string(),
-        ]);
+        let forest = example_forest();
         let slope = (3, 1);
 
-        assert_eq!(count_trees(&input, slope), 7)
+        assert_eq!(forest.count_trees(slope), 7)
     }
 
     #[test]
     fn test_count_trees_with_different_slopes() {
-        let input = Vec::from([
-            "..##.......".to_string(),
-            "#...#...#..".to_string(),
-            ".#....#..#.".to_string(),
-            "..#.#...#.#".to_string(),
-            ".#...##..#.".to_string(),
-            "..#.##.....".to_string(),
-            ".#.#.#....#".to_string(),
-            ".#........#".to_string(),
-            "#.##...#...".to_string(),
-            "#...##....#".to_string(),
-            ".#..#...#.#".to_string(),
-        ]);
+        let forest = example_forest();
 
-        assert_eq!(count_trees(&input, (1, 1)), 2);
-        assert_eq!(count_trees(&input, (5, 1)), 3);
-        assert_eq!(count_trees(&input, (7, 1)), 4);
-        assert_eq!(count_trees(&input, (1, 2)), 2);
+        assert_eq!(forest.count_trees((1, 1)), 2);
+        assert_eq!(forest.count_trees((5, 1)), 3);
+        assert_eq!(forest.count_trees((7, 1)), 4);
+        assert_eq!(forest.count_trees((1, 2)), 2);
     }
 
     #[test]
-    fn test_get_square() {
-        let input = Vec::from([
-            "..##.......".to_string(),
-            "#...#...#..".to_string(),
-            ".#....#..#.".to_string(),
-            "..#.#...#.#".to_string(),
-        ]);
-        let grid = create_grid(&input);
-        assert_eq!(get_square(&grid, (3, 1)), Some(&Square::Open));
-        assert_eq!(get_square(&grid, (1, 2)), Some(&Square::Tree))
+    fn test_is_tree() {
+        let forest = Forest::parse(&example_input()[..4]).expect("Map should parse");
+        assert!(!forest.is_tree(3, 1));
+        assert!(forest.is_tree(1, 2));
+        assert!(!forest.is_tree(11, 2))
     }
 
     #[test]
-    fn test_get_square_with_wrapping() {
-        let input = Vec::from([
-            "..##.......".to_string(),
-            "#...#...#..".to_string(),
-            ".#....#..#.".to_string(),
-            "..#.#...#.#".to_string(),
-            ".#...##..#.".to_string(),
-            "..#.##.....".to_string(),
-        ]);
-        let grid = create_grid(&input);
-        assert_eq!(get_square(&grid, (12, 4)), Some(&Square::Tree));
-        assert_eq!(get_square(&grid, (15, 5)), Some(&Square::Tree))
+    fn test_visited_trees_with_wrapping() {
+        let forest = Forest::parse(&example_input()[..6]).expect("Map should parse");
+        let visited = forest.visited(&Journey::new((3, 1))).collect::<Vec<_>>();
+        assert_eq!(visited[3], ((12, 4), true));
+        assert_eq!(visited[4], ((15, 5), true))
     }
 
     #[test]
@@ -275,7 +516,16 @@ mod test {
 
     #[test]
     fn test_tree_product() {
-        let input = Vec::from([
+        let slopes = Vec::from([(1, 1), (3, 1), (5, 1), (7, 1), (1, 2)]);
+        let forest = example_forest();
+        assert_eq!(
+            forest.tree_product(&slopes, Precision::Bits64),
+            Ok(Answer::Small(336))
+        )
+    }
+
+    pub(crate) fn example_input() -> Vec<String> {
+        Vec::from([
             "..##.......".to_string(),
             "#...#...#..".to_string(),
             ".#....#..#.".to_string(),
@@ -287,8 +537,134 @@ mod test {
             "#.##...#...".to_string(),
             "#...##....#".to_string(),
             ".#..#...#.#".to_string(),
-        ]);
-        let slopes = Vec::from([(1, 1), (3, 1), (5, 1), (7, 1), (1, 2)]);
-        assert_eq!(tree_product(&input, &slopes), 336)
+        ])
+    }
+
+    pub(crate) fn example_forest() -> Forest {
+        Forest::parse(&example_input()).expect("Map should parse")
+    }
+
+    // Walks a journey one step at a time, remembering every square it lands
+    // on, to check the step counts Forest::visited works out in advance.
+    fn simulated_positions(forest: &Forest, journey: &Journey) -> Vec<Position> {
+        let resolve = |(x, y): Position| {
+            Some((
+                journey.edges.horizontal.resolve(x, forest.width())?,
+                journey.edges.vertical.resolve(y, forest.height())?,
+            ))
+        };
+        let mut position = (0, 0);
+        let mut visited = HashSet::new();
+        visited.extend(resolve(position));
+        let mut positions = Vec::new();
+        while let Some(next) = move_tobogan(position, journey.slope) {
+            match resolve(next) {
+                Some(square) if visited.insert(square) => (),
+                _ => break,
+            }
+            position = next;
+            positions.push(position);
+        }
+        positions
+    }
+
+    #[test]
+    fn test_visited_matches_simulation() {
+        let forest = example_forest();
+        let policies = [EdgePolicy::Wrap, EdgePolicy::Clamp, EdgePolicy::Bounded];
+        for horizontal in policies {
+            for vertical in policies {
+                for dy in -12..=12 {
+                    for dx in -12..=12 {
+                        let journey = Journey {
+                            edges: Edges {
+                                horizontal,
+                                vertical,
+                            },
+                            ..Journey::new((dx, dy))
+                        };
+                        assert_eq!(
+                            positions(&forest, &journey),
+                            simulated_positions(&forest, &journey),
+                            "{:?}",
+                            journey
+                        );
+                    }
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_wide_rows() {
+        let row = |tree| {
+            let mut line = ".".repeat(70);
+            line.replace_range(tree..tree + 1, "#");
+            line
+        };
+        let forest = Forest::parse(&[row(0), row(65), row(69)]).expect("Map should parse");
+        assert!(forest.is_tree(65, 1) && forest.is_tree(69, 2));
+        assert!(!forest.is_tree(1, 1) && !forest.is_tree(64, 1));
+        assert_eq!(forest.count_trees((65, 1)), 1);
+        assert_eq!(forest.count_trees((-1, 2)), 1)
+    }
+
+    #[test]
+    fn test_tree_product_overflow() {
+        let row = "#".repeat(100);
+        let forest = Forest::parse(&vec![row; 1001]).expect("Map should parse");
+        // Each slope (1, 1) hits 1000 trees, and 1000^7 needs more than 64 bits.
+        let slopes = [(1, 1); 7];
+        assert_eq!(
+            forest.tree_product(&slopes, Precision::Bits64),
+            Err(OverflowError {
+                precision: Precision::Bits64
+            })
+        );
+        assert_eq!(
+            forest
+                .tree_product(&slopes, Precision::Bits128)
+                .map(|answer| answer.to_string()),
+            Ok("1000000000000000000000".to_string())
+        );
+        // Slope (1, 100) hits 10 trees, and 10^19 fits in a u64 but not an i64.
+        let slopes = [(1, 1), (1, 1), (1, 1), (1, 1), (1, 1), (1, 1), (1, 100)];
+        assert_eq!(
+            forest
+                .tree_product(&slopes, Precision::Bits64)
+                .map(|answer| answer.to_string()),
+            Ok("10000000000000000000".to_string())
+        )
+    }
+
+    #[test]
+    fn test_empty_forest() {
+        let forest = Forest::parse(&[]).expect("An empty map is rectangular");
+        assert_eq!(forest.count_trees((3, 1)), 0);
+        assert_eq!(
+            forest.tree_product(&[(1, 1), (3, 1)], Precision::Bits64),
+            Ok(Answer::Small(0))
+        )
+    }
+
+    #[test]
+    fn test_rank_slopes() {
+        let forest = example_forest();
+        let most = forest.rank_slopes(1..=7, 1..=2, Ranking::MostTrees);
+        assert_eq!(
+            most[0],
+            SlopeTrees {
+                slope: (3, 1),
+                squares: 10,
+                trees: 7
+            }
+        );
+        assert_eq!(most.len(), 14);
+        let fewest = forest.rank_slopes(1..=7, 1..=2, Ranking::FewestTrees);
+        assert!(fewest.windows(2).all(|w| w[0].trees <= w[1].trees));
+        assert_eq!(fewest[0].trees, 0);
+        assert!(forest
+            .rank_slopes(0..=0, -1..=0, Ranking::FewestTrees)
+            .is_empty())
     }
 }
@@ -0,0 +1,285 @@
+use super::{Forest, Journey, Position, MAP_EDGES};
+use crate::grid::Edges;
+use std::{error::Error, fmt, fmt::Write};
+
+// Colours for successive routes in an SVG, reused once they run out.
+const ROUTE_COLOURS: [&str; 6] = [
+    "#d62728", "#1f77b4", "#ff7f0e", "#9467bd", "#17becf", "#e377c2",
+];
+const CELL_SIZE: isize = 10;
+// The most squares a drawing may cover. A steep slope can land a long way
+// from the map, and everything in between would be drawn.
+const MAX_SQUARES: usize = 1 << 20;
+
+type Route = Vec<(Position, bool)>;
+
+// The squares to draw, inclusive on all sides. It always covers the map
+// itself and grows to take in every square on the routes.
+#[derive(Debug, Eq, PartialEq)]
+struct Extent {
+    left: isize,
+    top: isize,
+    right: isize,
+    bottom: isize,
+}
+
+fn extent(forest: &Forest, routes: &[Route]) -> Extent {
+    let mut extent = Extent {
+        left: 0,
+        top: 0,
+        right: forest.width() as isize - 1,
+        bottom: forest.height() as isize - 1,
+    };
+    for &((x, y), _) in routes.iter().flatten() {
+        extent.left = extent.left.min(x);
+        extent.right = extent.right.max(x);
+        extent.top = extent.top.min(y);
+        extent.bottom = extent.bottom.max(y);
+    }
+    extent
+}
+
+impl Extent {
+    // The width and height in squares, or None if there are more than
+    // MAX_SQUARES of them.
+    fn size(&self) -> Option<(usize, usize)> {
+        let width = self.right.checked_sub(self.left)?.checked_add(1)? as usize;
+        let height = self.bottom.checked_sub(self.top)?.checked_add(1)? as usize;
+        match width.checked_mul(height) {
+            Some(squares) if squares <= MAX_SQUARES => Some((width, height)),
+            _ => None,
+        }
+    }
+}
+
+// A route that reaches too far from the map to draw.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct TooLargeError {
+    pub limit: usize,
+}
+
+impl fmt::Display for TooLargeError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "drawing the route would take more than {} squares",
+            self.limit
+        )
+    }
+}
+
+impl Error for TooLargeError {}
+
+fn checked_extent(
+    forest: &Forest,
+    routes: &[Route],
+) -> Result<(Extent, usize, usize), TooLargeError> {
+    let extent = extent(forest, routes);
+    match extent.size() {
+        Some((width, height)) => Ok((extent, width, height)),
+        None => Err(TooLargeError { limit: MAX_SQUARES }),
+    }
+}
+
+// Whether the square at a position holds a tree, or None beyond a bounded
+// edge where there is no map to draw.
+fn tree_at(forest: &Forest, edges: Edges, (x, y): Position) -> Option<bool> {
+    let x = edges.horizontal.resolve(x, forest.width())?;
+    let y = edges.vertical.resolve(y, forest.height())?;
+    Some(forest.is_tree(x, y))
+}
+
+// Draws the map repeated as far as the route goes, one line per row, with the
+// squares the toboggan lands on marked as in the puzzle: X where it hits a
+// tree and O where it misses.
+pub fn render_ascii(forest: &Forest, journey: &Journey) -> Result<String, TooLargeError> {
+    let route = forest.visited(journey).collect::<Route>();
+    let (extent, width, height) = checked_extent(forest, std::slice::from_ref(&route))?;
+    let mut output = String::with_capacity((width + 1) * height);
+    for y in extent.top..=extent.bottom {
+        let mut line = (extent.left..=extent.right)
+            .map(|x| match tree_at(forest, journey.edges, (x, y)) {
+                Some(true) => '#',
+                Some(false) => '.',
+                None => ' ',
+            })
+            .collect::<Vec<char>>();
+        for &((x, row), tree) in &route {
+            if row == y {
+                line[(x - extent.left) as usize] = if tree { 'X' } else { 'O' };
+            }
+        }
+        output.extend(line);
+        output.push('\n');
+    }
+    Ok(output)
+}
+
+// Draws the map repeated as far as any route goes, with each route as a line
+// in its own colour from the start square through every square it lands on.
+// Hits are filled circles and misses are hollow. The map is repeated using
+// the first journey's edges.
+pub fn render_svg(forest: &Forest, journeys: &[Journey]) -> Result<String, TooLargeError> {
+    let routes = journeys
+        .iter()
+        .map(|journey| forest.visited(journey).collect::<Route>())
+        .collect::<Vec<Route>>();
+    // Every position drawn lies within the extent, and the extent is small
+    // enough that its size in pixels fits, so the sums below cannot overflow.
+    let (extent, width, height) = checked_extent(forest, &routes)?;
+    let edges = journeys.first().map_or(MAP_EDGES, |journey| journey.edges);
+    let centre = |(x, y): Position| {
+        (
+            (x - extent.left) * CELL_SIZE + CELL_SIZE / 2,
+            (y - extent.top) * CELL_SIZE + CELL_SIZE / 2,
+        )
+    };
+
+    let mut svg = String::new();
+    let width = width * CELL_SIZE as usize;
+    let height = height * CELL_SIZE as usize;
+    // Writing to a String cannot fail.
+    let _ = writeln!(
+        svg,
+        r#"<svg xmlns="http://www.w3.org/2000/svg" width="{0}" height="{1}" viewBox="0 0 {0} {1}">"#,
+        width, height
+    );
+    let _ = writeln!(
+        svg,
+        r##"<rect width="{}" height="{}" fill="#f4f1e8"/>"##,
+        width, height
+    );
+    for y in extent.top..=extent.bottom {
+        for x in extent.left..=extent.right {
+            if tree_at(forest, edges, (x, y)) == Some(true) {
+                let _ = writeln!(
+                    svg,
+                    r##"<rect x="{}" y="{}" width="{2}" height="{2}" fill="#2e7d32"/>"##,
+                    (x - extent.left) * CELL_SIZE,
+                    (y - extent.top) * CELL_SIZE,
+                    CELL_SIZE
+                );
+            }
+        }
+    }
+    for (route, colour) in routes.iter().zip(ROUTE_COLOURS.iter().cycle()) {
+        let points = std::iter::once((0, 0))
+            .chain(route.iter().map(|(position, _)| *position))
+            .map(|position| {
+                let (x, y) = centre(position);
+                format!("{},{}", x, y)
+            })
+            .collect::<Vec<String>>()
+            .join(" ");
+        let _ = writeln!(
+            svg,
+            r#"<polyline points="{}" fill="none" stroke="{}" stroke-width="2"/>"#,
+            points, colour
+        );
+        for &(position, tree) in route {
+            let (x, y) = centre(position);
+            let fill = if tree { colour } else { "none" };
+            let _ = writeln!(
+                svg,
+                r#"<circle cx="{}" cy="{}" r="3" fill="{}" stroke="{}"/>"#,
+                x, y, fill, colour
+            );
+        }
+    }
+    svg.push_str("</svg>\n");
+    Ok(svg)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{day_3::test::example_forest, grid::EdgePolicy};
+
+    #[test]
+    fn test_render_ascii() {
+        let rendered =
+            render_ascii(&example_forest(), &Journey::new((3, 1))).expect("Small enough");
+        let lines = rendered.lines().collect::<Vec<&str>>();
+        assert_eq!(
+            lines,
+            [
+                "..##.........##.........##.....",
+                "#..O#...#..#...#...#..#...#...#",
+                ".#....X..#..#....#..#..#....#..",
+                "..#.#...#O#..#.#...#.#..#.#...#",
+                ".#...##..#..X...##..#..#...##..",
+                "..#.##.......#.X#.......#.##...",
+                ".#.#.#....#.#.#.#.O..#.#.#.#...",
+                ".#........#.#........X.#.......",
+                "#.##...#...#.##...#...#.X#...#.",
+                "#...##....##...##....##...#X...",
+                ".#..#...#.#.#..#...#.#.#..#...X",
+            ]
+        )
+    }
+
+    #[test]
+    fn test_render_ascii_going_left_and_up() {
+        let forest =
+            Forest::parse(&["#..".to_string(), "...".to_string()]).expect("Map should parse");
+        assert_eq!(
+            render_ascii(&forest, &Journey::new((-2, 1))),
+            Ok("..#..\nO....\n".to_string())
+        );
+        let journey = Journey {
+            edges: Edges::from(EdgePolicy::Wrap),
+            step_limit: Some(2),
+            ..Journey::new((1, -1))
+        };
+        assert_eq!(
+            render_ascii(&forest, &journey),
+            Ok("#.O\n.O.\n#..\n...\n".to_string())
+        )
+    }
+
+    #[test]
+    fn test_render_svg() {
+        let forest = example_forest();
+        let svg = render_svg(&forest, &[Journey::new((3, 1)), Journey::new((1, 2))])
+            .expect("Small enough");
+        assert!(svg.starts_with(
+            r#"<svg xmlns="http://www.w3.org/2000/svg" width="310" height="110" viewBox="0 0 310 110">"#
+        ));
+        assert!(svg.ends_with("</svg>\n"));
+        assert_eq!(svg.matches("<polyline").count(), 2);
+        assert!(svg.contains(r#"<polyline points="5,5 35,15 65,25"#));
+        assert!(svg.contains(r##"stroke="#1f77b4""##));
+        // Ten landings for (3, 1) and five for (1, 2).
+        assert_eq!(svg.matches("<circle").count(), 15);
+        assert_eq!(svg.matches(r##"fill="#d62728""##).count(), 7);
+    }
+
+    #[test]
+    fn test_huge_slopes_are_too_large_to_draw() {
+        let forest = example_forest();
+        let error = TooLargeError { limit: MAX_SQUARES };
+        assert_eq!(
+            render_ascii(&forest, &Journey::new((isize::MAX, 1))),
+            Err(error)
+        );
+        assert_eq!(
+            render_ascii(&forest, &Journey::new((isize::MIN, 1))),
+            Err(error)
+        );
+        assert_eq!(
+            render_svg(&forest, &[Journey::new((isize::MAX / 2, 1))]),
+            Err(error)
+        );
+        assert_eq!(
+            render_svg(
+                &forest,
+                &[Journey::new((3, 1)), Journey::new((isize::MIN / 2, 1))]
+            ),
+            Err(error)
+        );
+        assert_eq!(
+            error.to_string(),
+            "drawing the route would take more than 1048576 squares"
+        );
+    }
+}
@@ -0,0 +1,274 @@
+use super::{move_tobogan, Journey, Position};
+use crate::grid::{EdgePolicy, Grid, GridError};
+use std::collections::{HashMap, HashSet};
+
+// What happens to the toboggan when it lands on a kind of terrain.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Effect {
+    // Carries on as normal.
+    Pass,
+    // Stops dead, ending the ride on this square.
+    Stop,
+    // Slides, so the next step covers twice the slope.
+    DoubleStep,
+}
+
+// A kind of terrain and what it costs to land on it.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct Terrain {
+    pub cost: u64,
+    pub effect: Effect,
+}
+
+impl Terrain {
+    pub fn new(cost: u64, effect: Effect) -> Terrain {
+        Terrain { cost, effect }
+    }
+}
+
+// Which character stands for which terrain in a map.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct Legend {
+    terrain: HashMap<char, Terrain>,
+}
+
+impl Legend {
+    pub fn new() -> Legend {
+        Legend::default()
+    }
+
+    // The puzzle's map: open ground is free and each tree costs one, so the
+    // total cost of a ride is the number of trees hit.
+    pub fn puzzle() -> Legend {
+        let mut legend = Legend::new();
+        legend.insert('.', Terrain::new(0, Effect::Pass));
+        legend.insert('#', Terrain::new(1, Effect::Pass));
+        legend
+    }
+
+    // Returns the terrain the character stood for before, if any.
+    pub fn insert(&mut self, symbol: char, terrain: Terrain) -> Option<Terrain> {
+        self.terrain.insert(symbol, terrain)
+    }
+
+    pub fn get(&self, symbol: char) -> Option<Terrain> {
+        self.terrain.get(&symbol).copied()
+    }
+}
+
+// How a ride went: the squares landed on, what they cost in total, and where
+// the toboggan came to a stop if it hit terrain that stops it.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct Ride {
+    pub squares: u64,
+    pub cost: u64,
+    pub stopped_at: Option<Position>,
+}
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct TerrainMap {
+    grid: Grid<Terrain>,
+}
+
+impl TerrainMap {
+    // Rejects any character the legend does not know, giving its row and
+    // column.
+    pub fn parse(input: &[String], legend: &Legend) -> Result<TerrainMap, GridError> {
+        Ok(TerrainMap {
+            grid: Grid::from_chars(input, |c| legend.get(c))?,
+        })
+    }
+
+    pub fn width(&self) -> usize {
+        self.grid.width()
+    }
+
+    pub fn height(&self) -> usize {
+        self.grid.height()
+    }
+
+    // Rides from (0, 0) following the journey, paying for every square landed
+    // on. The ride ends on terrain that stops the toboggan, when a step leaves
+    // the map over a bounded edge, or as for Forest::visited when it would
+    // land on a square it has already visited or reaches the step limit. A
+    // toboggan pushed past a clamped edge stays on the edge.
+    pub fn ride(&self, journey: &Journey) -> Ride {
+        let mut ride = Ride {
+            squares: 0,
+            cost: 0,
+            stopped_at: None,
+        };
+        let mut position = (0, 0);
+        let mut visited = HashSet::new();
+        let mut square = match self.grid.resolve(0, 0, journey.edges) {
+            Some(square) => square,
+            None => return ride,
+        };
+        visited.insert(square);
+        while journey
+            .step_limit
+            .is_none_or(|limit| ride.squares < limit as u64)
+        {
+            let step = match self.grid[square].effect {
+                Effect::DoubleStep => match (
+                    journey.slope.0.checked_mul(2),
+                    journey.slope.1.checked_mul(2),
+                ) {
+                    (Some(dx), Some(dy)) => (dx, dy),
+                    _ => break,
+                },
+                _ => journey.slope,
+            };
+            position = match move_tobogan(position, step) {
+                Some(position) => position,
+                None => break,
+            };
+            square = match self.grid.resolve(position.0, position.1, journey.edges) {
+                Some(square) => square,
+                None => break,
+            };
+            if journey.step_limit.is_none() && !visited.insert(square) {
+                break;
+            }
+            if journey.edges.horizontal == EdgePolicy::Clamp {
+                position.0 = square.0 as isize;
+            }
+            if journey.edges.vertical == EdgePolicy::Clamp {
+                position.1 = square.1 as isize;
+            }
+            let terrain = self.grid[square];
+            ride.squares += 1;
+            ride.cost += terrain.cost;
+            if terrain.effect == Effect::Stop {
+                ride.stopped_at = Some(position);
+                break;
+            }
+        }
+        ride
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::day_3::{test::example_input, Forest};
+
+    fn legend_with_rock_and_ice() -> Legend {
+        let mut legend = Legend::puzzle();
+        legend.insert('R', Terrain::new(5, Effect::Stop));
+        legend.insert('~', Terrain::new(0, Effect::DoubleStep));
+        legend
+    }
+
+    #[test]
+    fn test_puzzle_legend_costs_match_tree_counts() {
+        let input = example_input();
+        let map = TerrainMap::parse(&input, &Legend::puzzle()).expect("Map should parse");
+        let forest = Forest::parse(&input).expect("Map should parse");
+        for slope in [(1, 1), (3, 1), (5, 1), (7, 1), (1, 2), (-2, 3), (4, 0)] {
+            let ride = map.ride(&Journey::new(slope));
+            assert_eq!(ride.cost, forest.count_trees(slope));
+            assert_eq!(
+                ride.squares,
+                forest.visited(&Journey::new(slope)).count() as u64
+            );
+            assert_eq!(ride.stopped_at, None);
+        }
+    }
+
+    #[test]
+    fn test_parse_rejects_unknown_characters() {
+        let input = Vec::from(["..#".to_string(), ".R.".to_string()]);
+        assert_eq!(
+            TerrainMap::parse(&input, &Legend::puzzle()),
+            Err(GridError::UnexpectedCharacter {
+                row: 2,
+                column: 2,
+                found: 'R'
+            })
+        );
+        let map = TerrainMap::parse(&input, &legend_with_rock_and_ice()).expect("Map should parse");
+        assert_eq!((map.width(), map.height()), (3, 2));
+    }
+
+    #[test]
+    fn test_rock_stops_the_toboggan() {
+        let input = Vec::from([
+            "....".to_string(),
+            ".#..".to_string(),
+            "..R.".to_string(),
+            "...#".to_string(),
+        ]);
+        let map = TerrainMap::parse(&input, &legend_with_rock_and_ice()).expect("Map should parse");
+        assert_eq!(
+            map.ride(&Journey::new((1, 1))),
+            Ride {
+                squares: 2,
+                cost: 6,
+                stopped_at: Some((2, 2))
+            }
+        );
+    }
+
+    #[test]
+    fn test_ice_doubles_the_next_step() {
+        let input = Vec::from([
+            "......".to_string(),
+            ".~....".to_string(),
+            "......".to_string(),
+            "...#..".to_string(),
+            "....#.".to_string(),
+        ]);
+        let map = TerrainMap::parse(&input, &legend_with_rock_and_ice()).expect("Map should parse");
+        // Lands on the ice at (1, 1), slides to (3, 3) and steps on to (4, 4).
+        assert_eq!(
+            map.ride(&Journey::new((1, 1))),
+            Ride {
+                squares: 3,
+                cost: 2,
+                stopped_at: None
+            }
+        );
+    }
+
+    #[test]
+    fn test_ride_ends_when_the_position_overflows() {
+        let input = Vec::from(["~#.".to_string(), "..#".to_string()]);
+        let map = TerrainMap::parse(&input, &legend_with_rock_and_ice()).expect("Map should parse");
+        // The ice at the start doubles a step that is already too long.
+        assert_eq!(
+            map.ride(&Journey::new((isize::MAX, 1))),
+            Ride {
+                squares: 0,
+                cost: 0,
+                stopped_at: None
+            }
+        );
+        let journey = Journey {
+            step_limit: Some(5),
+            ..Journey::new((isize::MAX / 2, 0))
+        };
+        assert_eq!(map.ride(&journey).squares, 1);
+    }
+
+    #[test]
+    fn test_ice_loop_ends_without_a_step_limit() {
+        let input = Vec::from(["~#.~".to_string()]);
+        let map = TerrainMap::parse(&input, &legend_with_rock_and_ice()).expect("Map should parse");
+        // From the ice at (0, 0) it slides to (2, 0), then (3, 0) which is
+        // ice, then wraps round to (1, 0) and on to (2, 0) again.
+        assert_eq!(
+            map.ride(&Journey::new((1, 0))),
+            Ride {
+                squares: 3,
+                cost: 1,
+                stopped_at: None
+            }
+        );
+        let journey = Journey {
+            step_limit: Some(7),
+            ..Journey::new((1, 0))
+        };
+        assert_eq!(map.ride(&journey).squares, 7);
+    }
+}
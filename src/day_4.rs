@@ -1,24 +1,36 @@
-use regex::Regex;
 use std::{
     collections::{HashMap, HashSet},
     convert::TryInto,
-    fs::File,
     io,
-    io::{BufRead, BufReader},
+    sync::OnceLock,
 };
 
+mod export;
+mod passport;
+mod records;
+mod redact;
+mod report;
+mod schema;
+
+pub use export::{export_records, import_records, ExportFormat, ImportError, Selection};
+pub use passport::{
+    parse_passports, EyeColour, FieldError, FieldErrorKind, HairColour, Height, ParseFieldError,
+    Passport, PassportError, PassportId, Year,
+};
+pub use records::{
+    parse_records, read_records, ParsedRecords, Record, RecordField, RecordWarning, WarningKind,
+};
+pub use redact::{redact_records, write_records, Redactor};
+pub use report::{validation_report, PassportReport, Problem, ValidationReport};
+pub use schema::{
+    count_valid_passports_with_schema, Field, Rule, Schema, SchemaError, UnitRange, Violation,
+    ViolationKind,
+};
+
+// Tokens without a colon are skipped, and a key given twice keeps its last
+// value. Use parse_records to hear about either.
 fn read_passport_data(input: &[String]) -> HashMap<String, String> {
-    input
-        .iter()
-        .flat_map(|line| line.split_whitespace())
-        .map(|i| i.split_terminator(':').collect::<Vec<&str>>())
-        .map(|kv| {
-            (
-                kv.get(0).expect("Missing key/value").to_string(),
-                (kv.get(1).expect("Missing key/value").to_string()),
-            )
-        })
-        .collect()
+    Record::from_lines(input.to_vec(), 1, &mut Vec::new()).to_map()
 }
 
 #[derive(Copy, Clone)]
@@ -27,6 +39,22 @@ pub enum CountType {
     KeysAndValues,
 }
 
+// The puzzle's rules, read once. The key and value checks below both come
+// from it, so the counts and the schema cannot disagree.
+fn puzzle_schema() -> &'static Schema {
+    static PUZZLE: OnceLock<Schema> = OnceLock::new();
+    PUZZLE.get_or_init(Schema::puzzle)
+}
+
+fn puzzle_rule(key: &str) -> &'static Rule {
+    puzzle_schema()
+        .fields
+        .iter()
+        .find(|field| field.key == key)
+        .and_then(|field| field.rule.as_ref())
+        .expect("The puzzle schema has a rule for every checked field")
+}
+
 fn valid_passport_data(input: &HashMap<String, String>, count_type: CountType) -> bool {
     let keys = input.keys().map(|s| s.as_ref()).collect::<HashSet<&str>>();
     match count_type {
@@ -38,57 +66,24 @@ fn valid_passport_data(input: &HashMap<String, String>, count_type: CountType) -
 }
 
 fn passport_keys_are_valid(passport_keys: HashSet<&str>) -> bool {
-    let mut valid_keys = HashSet::new();
-    valid_keys.insert("ecl");
-    valid_keys.insert("pid");
-    valid_keys.insert("eyr");
-    valid_keys.insert("hcl");
-    valid_keys.insert("byr");
-    valid_keys.insert("iyr");
-    valid_keys.insert("hgt");
-
-    passport_keys.is_superset(&valid_keys)
+    puzzle_schema()
+        .fields
+        .iter()
+        .filter(|field| field.required)
+        .all(|field| passport_keys.contains(field.key.as_str()))
 }
 
 fn passport_values_are_valid(passport_data: &HashMap<String, String>) -> bool {
     for key in passport_data.keys() {
         let value = passport_data.get(key).expect("No value");
         match key.as_ref() {
-            "ecl" => {
-                if !valid_eye_colour(value) {
-                    return false;
-                }
-            }
-            "pid" => {
-                if !valid_passport_number(value) {
-                    return false;
-                }
-            }
-            "eyr" => {
-                if !valid_expiration_year(value) {
-                    return false;
-                }
-            }
-            "hcl" => {
-                if !valid_hair_colour(value) {
-                    return false;
-                }
-            }
-            "byr" => {
-                if !valid_birth_year(value) {
-                    return false;
-                }
-            }
-            "iyr" => {
-                if !valid_issue_year(value) {
-                    return false;
-                }
-            }
-            "hgt" => {
-                if !valid_height(value) {
-                    return false;
-                }
-            }
+            "ecl" if !valid_eye_colour(value) => return false,
+            "pid" if !valid_passport_number(value) => return false,
+            "eyr" if !valid_expiration_year(value) => return false,
+            "hcl" if !valid_hair_colour(value) => return false,
+            "byr" if !valid_birth_year(value) => return false,
+            "iyr" if !valid_issue_year(value) => return false,
+            "hgt" if !valid_height(value) => return false,
             _ => (),
         }
     }
@@ -96,89 +91,45 @@ fn passport_values_are_valid(passport_data: &HashMap<String, String>) -> bool {
 }
 
 fn valid_birth_year(birth_year: &str) -> bool {
-    valid_number(birth_year, 1920, 2002)
+    puzzle_rule("byr").check(birth_year)
 }
 
 fn valid_issue_year(issue_year: &str) -> bool {
-    valid_number(issue_year, 2010, 2020)
+    puzzle_rule("iyr").check(issue_year)
 }
 
 fn valid_expiration_year(expiration_year: &str) -> bool {
-    valid_number(expiration_year, 2020, 2030)
-}
-
-fn valid_number(number: &str, min: u32, max: u32) -> bool {
-    let number = match number.parse::<u32>() {
-        Ok(nmb) => nmb,
-        Err(_) => return false,
-    };
-    number >= min && number <= max
+    puzzle_rule("eyr").check(expiration_year)
 }
 
 fn valid_height(height: &str) -> bool {
-    if height.is_empty() {
-        return false;
-    }
-    match height.get(height.len() - 2..height.len()) {
-        Some(dimension) => match dimension {
-            "cm" => match height.get(0..3) {
-                Some(number) => valid_number(number, 150, 193),
-                None => false,
-            },
-            "in" => match height.get(0..2) {
-                Some(number) => valid_number(number, 59, 76),
-                None => false,
-            },
-            _ => false,
-        },
-        None => false,
-    }
+    puzzle_rule("hgt").check(height)
 }
 
 fn valid_hair_colour(colour: &str) -> bool {
-    let reg = Regex::new(r"^#([0-9]|[a-f]){6}").expect("Unable to create Regex");
-    reg.is_match(colour)
+    puzzle_rule("hcl").check(colour)
 }
 
 fn valid_eye_colour(colour: &str) -> bool {
-    let valid_colours = Vec::from(["amb", "blu", "brn", "gry", "grn", "hzl", "oth"]);
-    valid_colours.contains(&colour)
+    puzzle_rule("ecl").check(colour)
 }
 
 fn valid_passport_number(number: &str) -> bool {
-    if number.len() == 9 {
-        number.parse::<u32>().is_ok()
-    } else {
-        false
-    }
+    puzzle_rule("pid").check(number)
 }
 
 pub fn load_input_file(file_name: &str) -> io::Result<Vec<Vec<String>>> {
-    let input = File::open(file_name)?;
-    let reader = BufReader::new(input);
-    let mut passports = Vec::new();
-    let mut passport = Vec::new();
-    for line in reader.lines() {
-        match line {
-            Ok(l) => {
-                if l.is_empty() {
-                    passports.push(passport.clone());
-                    passport.clear();
-                } else {
-                    passport.push(l)
-                }
-            }
-            Err(_) => panic!("No line to read"),
-        }
-    }
-    passports.push(passport);
-    Ok(passports)
+    Ok(read_records(file_name)?
+        .records
+        .into_iter()
+        .map(|record| record.text)
+        .collect())
 }
 
 pub fn count_valid_passports(passport_data: &[Vec<String>], count_type: CountType) -> u32 {
     passport_data
         .iter()
-        .filter(|p| valid_passport_data(&read_passport_data(&p), count_type))
+        .filter(|p| valid_passport_data(&read_passport_data(p), count_type))
         .count()
         .try_into()
         .expect("Can't convert usize to us32")
@@ -187,6 +138,27 @@ pub fn count_valid_passports(passport_data: &[Vec<String>], count_type: CountTyp
 #[cfg(test)]
 mod test {
     use super::*;
+
+    use crate::random::Lcg;
+
+    // Puts one of the characters somewhere in the value, keeping to char
+    // boundaries.
+    fn insert(rng: &mut Lcg, value: &str, choices: &str) -> String {
+        let c = rng.pick(choices);
+        let boundaries = value
+            .char_indices()
+            .map(|(i, _)| i)
+            .chain(std::iter::once(value.len()))
+            .collect::<Vec<usize>>();
+        let at = boundaries[rng.below(boundaries.len() as u64) as usize];
+        let mut value = value.to_string();
+        value.insert(at, c);
+        value
+    }
+
+    const PROPERTY_CASES: usize = 2000;
+    const JUNK: &str = "x+- #.\u{e9}\u{2603}";
+
     #[test]
     fn test_read_passport_data() {
         let passport_data = Vec::from([
@@ -218,10 +190,10 @@ mod test {
         passport_data.insert("iyr".into(), "2017".into());
         passport_data.insert("cid".into(), "147".into());
         passport_data.insert("hgt".into(), "183cm".into());
-        assert_eq!(
-            valid_passport_data(&passport_data, CountType::KeysAndValues),
-            true
-        )
+        assert!(valid_passport_data(
+            &passport_data,
+            CountType::KeysAndValues
+        ))
     }
 
     #[test]
@@ -234,10 +206,7 @@ mod test {
         passport_data.insert("byr".into(), "1937".into());
         passport_data.insert("iyr".into(), "2017".into());
         passport_data.insert("cid".into(), "147".into());
-        assert_eq!(
-            valid_passport_data(&passport_data, CountType::KeysOnly),
-            false
-        )
+        assert!(!valid_passport_data(&passport_data, CountType::KeysOnly))
     }
 
     #[test]
@@ -250,10 +219,7 @@ mod test {
         passport_data.insert("byr".into(), "1937".into());
         passport_data.insert("iyr".into(), "2017".into());
         passport_data.insert("hgt".into(), "183cm".into());
-        assert_eq!(
-            valid_passport_data(&passport_data, CountType::KeysOnly),
-            true
-        )
+        assert!(valid_passport_data(&passport_data, CountType::KeysOnly))
     }
 
     #[test]
@@ -314,7 +280,7 @@ mod test {
         passport_data.insert("iyr".into(), "2012".into());
         passport_data.insert("hgt".into(), "74in".into());
 
-        assert_eq!(passport_values_are_valid(&passport_data), true)
+        assert!(passport_values_are_valid(&passport_data))
     }
 
     #[test]
@@ -329,81 +295,183 @@ mod test {
         passport_data.insert("hgt".into(), "170".into());
         passport_data.insert("cid".into(), "100".into());
 
-        assert_eq!(passport_values_are_valid(&passport_data), false)
+        assert!(!passport_values_are_valid(&passport_data))
     }
 
     #[test]
     fn test_valid_birth_year() {
-        assert_eq!(valid_birth_year("2000"), true);
-        assert_eq!(valid_birth_year("number"), false);
-        assert_eq!(valid_birth_year("1919"), false);
-        assert_eq!(valid_birth_year("2003"), false);
-        assert_eq!(valid_birth_year("1920"), true);
-        assert_eq!(valid_birth_year("2002"), true);
-        assert_eq!(valid_birth_year("20025"), false);
-        assert_eq!(valid_birth_year("200"), false);
+        assert!(valid_birth_year("2000"));
+        assert!(!valid_birth_year("number"));
+        assert!(!valid_birth_year("1919"));
+        assert!(!valid_birth_year("2003"));
+        assert!(valid_birth_year("1920"));
+        assert!(valid_birth_year("2002"));
+        assert!(!valid_birth_year("20025"));
+        assert!(!valid_birth_year("02000"));
+        assert!(!valid_birth_year("0200"));
+        assert!(!valid_birth_year("200"));
     }
 
     #[test]
     fn test_valid_issue_year() {
-        assert_eq!(valid_issue_year("2015"), true);
-        assert_eq!(valid_issue_year("number"), false);
-        assert_eq!(valid_issue_year("2009"), false);
-        assert_eq!(valid_issue_year("2021"), false);
-        assert_eq!(valid_issue_year("2010"), true);
-        assert_eq!(valid_issue_year("2020"), true);
-        assert_eq!(valid_issue_year("20025"), false);
-        assert_eq!(valid_issue_year("200"), false);
+        assert!(valid_issue_year("2015"));
+        assert!(!valid_issue_year("number"));
+        assert!(!valid_issue_year("2009"));
+        assert!(!valid_issue_year("2021"));
+        assert!(valid_issue_year("2010"));
+        assert!(valid_issue_year("2020"));
+        assert!(!valid_issue_year("20025"));
+        assert!(!valid_issue_year("200"));
     }
 
     #[test]
     fn test_valid_expiration_year() {
-        assert_eq!(valid_expiration_year("2025"), true);
-        assert_eq!(valid_expiration_year("number"), false);
-        assert_eq!(valid_expiration_year("2019"), false);
-        assert_eq!(valid_expiration_year("2031"), false);
-        assert_eq!(valid_expiration_year("2020"), true);
-        assert_eq!(valid_expiration_year("2030"), true);
-        assert_eq!(valid_expiration_year("20025"), false);
-        assert_eq!(valid_expiration_year("200"), false);
+        assert!(valid_expiration_year("2025"));
+        assert!(!valid_expiration_year("number"));
+        assert!(!valid_expiration_year("2019"));
+        assert!(!valid_expiration_year("2031"));
+        assert!(valid_expiration_year("2020"));
+        assert!(valid_expiration_year("2030"));
+        assert!(!valid_expiration_year("20025"));
+        assert!(!valid_expiration_year("200"));
     }
 
     #[test]
     fn test_valid_height() {
-        assert_eq!(valid_height("60in"), true);
-        assert_eq!(valid_height("60"), false);
-        assert_eq!(valid_height("190cm"), true);
-        assert_eq!(valid_height("190in"), false);
-        assert_eq!(valid_height("77in"), false);
-        assert_eq!(valid_height("77km"), false);
-        assert_eq!(valid_height(""), false);
+        assert!(valid_height("60in"));
+        assert!(!valid_height("60"));
+        assert!(valid_height("190cm"));
+        assert!(!valid_height("190in"));
+        assert!(!valid_height("77in"));
+        assert!(!valid_height("77km"));
+        assert!(!valid_height(""));
+        assert!(!valid_height("150xcm"));
+        assert!(!valid_height("+60in"));
+        assert!(!valid_height("1500cm"));
+        assert!(!valid_height("cm"));
+        assert!(!valid_height("a"));
+        assert!(!valid_height("\u{e9}"));
+        assert!(!valid_height("1\u{e9}0cm"));
     }
 
     #[test]
     fn test_valid_hair_colour() {
-        assert_eq!(valid_hair_colour("#123abc"), true);
-        assert_eq!(valid_hair_colour("#123abz"), false);
-        assert_eq!(valid_hair_colour("123abc"), false);
-        assert_eq!(valid_hair_colour("#123456"), true);
-        assert_eq!(valid_hair_colour("#abcdef"), true);
+        assert!(valid_hair_colour("#123abc"));
+        assert!(!valid_hair_colour("#123abz"));
+        assert!(!valid_hair_colour("123abc"));
+        assert!(valid_hair_colour("#123456"));
+        assert!(valid_hair_colour("#abcdef"));
+        assert!(!valid_hair_colour("#123abcdef"));
+        assert!(!valid_hair_colour("#123ABC"));
+        assert!(!valid_hair_colour("#123ab"));
     }
 
     #[test]
     fn test_eye_colour() {
-        assert_eq!(valid_eye_colour("amb"), true);
-        assert_eq!(valid_eye_colour("blu"), true);
-        assert_eq!(valid_eye_colour("brn"), true);
-        assert_eq!(valid_eye_colour("gry"), true);
-        assert_eq!(valid_eye_colour("grn"), true);
-        assert_eq!(valid_eye_colour("hzl"), true);
-        assert_eq!(valid_eye_colour("oth"), true);
-        assert_eq!(valid_eye_colour("zod"), false);
+        assert!(valid_eye_colour("amb"));
+        assert!(valid_eye_colour("blu"));
+        assert!(valid_eye_colour("brn"));
+        assert!(valid_eye_colour("gry"));
+        assert!(valid_eye_colour("grn"));
+        assert!(valid_eye_colour("hzl"));
+        assert!(valid_eye_colour("oth"));
+        assert!(!valid_eye_colour("zod"));
     }
 
     #[test]
     fn test_valid_passport_number() {
-        assert_eq!(valid_passport_number("000000001"), true);
-        assert_eq!(valid_passport_number("0123456789"), false);
-        assert_eq!(valid_passport_number("abcdefghij"), false)
+        assert!(valid_passport_number("000000001"));
+        assert!(!valid_passport_number("0123456789"));
+        assert!(!valid_passport_number("abcdefghij"));
+        assert!(!valid_passport_number("+12345678"));
+        assert!(!valid_passport_number("-12345678"));
+    }
+
+    #[test]
+    fn test_generated_years() {
+        let mut rng = Lcg::new(0x0401);
+        for _ in 0..PROPERTY_CASES {
+            // Mostly four digit years, with some too short, too long or
+            // padded with leading zeros.
+            let year = rng.below(3000) as u32;
+            let zeros = ["", "", "", "0", "00"][rng.below(5) as usize];
+            let value = format!("{}{}", zeros, year);
+            let four_digits = value.len() == 4;
+            assert_eq!(
+                valid_birth_year(&value),
+                four_digits && (1920..=2002).contains(&year),
+                "{:?}",
+                value
+            );
+            assert_eq!(
+                valid_issue_year(&value),
+                four_digits && (2010..=2020).contains(&year),
+                "{:?}",
+                value
+            );
+            assert_eq!(
+                valid_expiration_year(&value),
+                four_digits && (2020..=2030).contains(&year),
+                "{:?}",
+                value
+            );
+            let junk = insert(&mut rng, &value, JUNK);
+            assert!(!valid_birth_year(&junk), "{:?}", junk);
+            assert!(!valid_issue_year(&junk), "{:?}", junk);
+            assert!(!valid_expiration_year(&junk), "{:?}", junk);
+        }
+    }
+
+    #[test]
+    fn test_generated_heights() {
+        let mut rng = Lcg::new(0x0402);
+        for _ in 0..PROPERTY_CASES {
+            let number = rng.below(250) as u32;
+            let unit = ["cm", "in", "", "km", "CM", "c"][rng.below(6) as usize];
+            let value = format!("{}{}", number, unit);
+            let expected = match unit {
+                "cm" => (150..=193).contains(&number),
+                "in" => (59..=76).contains(&number),
+                _ => false,
+            };
+            assert_eq!(valid_height(&value), expected, "{:?}", value);
+            let junk = insert(&mut rng, &value, JUNK);
+            assert!(!valid_height(&junk), "{:?}", junk);
+        }
+    }
+
+    #[test]
+    fn test_generated_hair_colours() {
+        let mut rng = Lcg::new(0x0403);
+        for _ in 0..PROPERTY_CASES {
+            let hex = rng.string(6, "0123456789abcdef");
+            assert!(valid_hair_colour(&format!("#{}", hex)), "{:?}", hex);
+            assert!(!valid_hair_colour(&hex), "{:?}", hex);
+            let extra = 1 + rng.below(3) as usize;
+            let longer = format!("#{}{}", hex, rng.string(extra, "0a"));
+            assert!(!valid_hair_colour(&longer), "{:?}", longer);
+            let shorter = format!("#{}", &hex[..rng.below(6) as usize]);
+            assert!(!valid_hair_colour(&shorter), "{:?}", shorter);
+            let mut wrong = hex.chars().collect::<Vec<char>>();
+            wrong[rng.below(6) as usize] = rng.pick("gzAF#\u{e9}");
+            let wrong = format!("#{}", wrong.into_iter().collect::<String>());
+            assert!(!valid_hair_colour(&wrong), "{:?}", wrong);
+        }
+    }
+
+    #[test]
+    fn test_generated_passport_numbers() {
+        let mut rng = Lcg::new(0x0404);
+        for _ in 0..PROPERTY_CASES {
+            let number = rng.string(9, "0123456789");
+            assert!(valid_passport_number(&number), "{:?}", number);
+            let length = [0, 1, 8, 10, 12][rng.below(5) as usize];
+            let other = rng.string(length, "0123456789");
+            assert!(!valid_passport_number(&other), "{:?}", other);
+            let mut wrong = number.chars().collect::<Vec<char>>();
+            wrong[rng.below(9) as usize] = rng.pick(JUNK);
+            let wrong = wrong.into_iter().collect::<String>();
+            assert!(!valid_passport_number(&wrong), "{:?}", wrong);
+        }
     }
 }
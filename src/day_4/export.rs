@@ -0,0 +1,453 @@
+use super::{CountType, Record, RecordField, Schema};
+use serde_json::{Map, Value};
+use std::{
+    error::Error,
+    fmt, io,
+    io::{BufRead, Read, Write},
+    str::FromStr,
+};
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum ExportFormat {
+    // One JSON object per line.
+    JsonLines,
+    Csv,
+}
+
+impl FromStr for ExportFormat {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "jsonl" | "json" => Ok(ExportFormat::JsonLines),
+            "csv" => Ok(ExportFormat::Csv),
+            _ => Err(format!("unknown format {:?}, expected jsonl or csv", s)),
+        }
+    }
+}
+
+// Which passports to export.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Selection {
+    All,
+    Valid,
+    Invalid,
+}
+
+impl FromStr for Selection {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "all" => Ok(Selection::All),
+            "valid" => Ok(Selection::Valid),
+            "invalid" => Ok(Selection::Invalid),
+            _ => Err(format!(
+                "unknown selection {:?}, expected all, valid or invalid",
+                s
+            )),
+        }
+    }
+}
+
+// Holds whether the passport was valid when exported. It is worked out again
+// from the fields on import.
+const VALID_COLUMN: &str = "valid";
+// Holds any fields the schema does not list: a nested object in JSON, and
+// space separated key:value pairs in CSV. Keeping them apart from the schema's
+// fields means a passport with its own valid or extra field still round trips.
+// In CSV it also holds schema fields given with an empty value, as "hgt:",
+// since their own cell is left empty when they are missing.
+const EXTRA_COLUMN: &str = "extra";
+
+// Writes the selected records, one per line or row. Fields are written in
+// schema order followed by any the schema does not list, then the validity.
+// Returns how many records were written, or an InvalidInput error if the
+// schema lists a key the export uses itself.
+pub fn export_records<W: Write>(
+    mut writer: W,
+    records: &[Record],
+    schema: &Schema,
+    count_type: CountType,
+    format: ExportFormat,
+    selection: Selection,
+) -> io::Result<usize> {
+    let selected = records
+        .iter()
+        .map(|record| (record, schema.is_valid(&record.to_map(), count_type)))
+        .filter(|(_, valid)| match selection {
+            Selection::All => true,
+            Selection::Valid => *valid,
+            Selection::Invalid => !*valid,
+        });
+    if let Some(field) = schema
+        .fields
+        .iter()
+        .find(|field| field.key == VALID_COLUMN || field.key == EXTRA_COLUMN)
+    {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!("the schema key {:?} is reserved for the export", field.key),
+        ));
+    }
+    let is_extra = |field: &&RecordField| schema.fields.iter().all(|f| f.key != field.key);
+    let mut written = 0;
+    match format {
+        ExportFormat::JsonLines => {
+            for (record, valid) in selected {
+                let mut object = Map::new();
+                for field in &schema.fields {
+                    let value = record
+                        .get(&field.key)
+                        .map_or(Value::Null, |f| Value::String(f.value.clone()));
+                    object.insert(field.key.clone(), value);
+                }
+                let extra = record
+                    .fields
+                    .iter()
+                    .filter(is_extra)
+                    .map(|field| (field.key.clone(), Value::String(field.value.clone())))
+                    .collect::<Map<String, Value>>();
+                object.insert(EXTRA_COLUMN.to_string(), Value::Object(extra));
+                object.insert(VALID_COLUMN.to_string(), Value::Bool(valid));
+                serde_json::to_writer(&mut writer, &object)?;
+                writeln!(writer)?;
+                written += 1;
+            }
+        }
+        ExportFormat::Csv => {
+            let mut csv = csv::Writer::from_writer(writer);
+            let mut header = schema
+                .fields
+                .iter()
+                .map(|field| field.key.as_str())
+                .collect::<Vec<&str>>();
+            header.extend([EXTRA_COLUMN, VALID_COLUMN]);
+            csv.write_record(&header)?;
+            for (record, valid) in selected {
+                let mut row = schema
+                    .fields
+                    .iter()
+                    .map(|field| record.get(&field.key).map_or("", |f| f.value.as_str()))
+                    .collect::<Vec<&str>>();
+                let extra = record
+                    .fields
+                    .iter()
+                    .filter(|field| is_extra(field) || field.value.is_empty())
+                    .map(|field| format!("{}:{}", field.key, field.value))
+                    .collect::<Vec<String>>()
+                    .join(" ");
+                row.push(&extra);
+                row.push(if valid { "true" } else { "false" });
+                csv.write_record(&row)?;
+                written += 1;
+            }
+            csv.flush()?;
+        }
+    }
+    Ok(written)
+}
+
+#[derive(Debug)]
+pub enum ImportError {
+    Io(io::Error),
+    Json {
+        line: usize,
+        error: serde_json::Error,
+    },
+    Csv(csv::Error),
+    // A JSON field that is neither a string nor null, or an extra field that
+    // is not a string.
+    UnexpectedValue {
+        line: usize,
+        key: String,
+    },
+    // A pair in the extra column without a colon.
+    MalformedExtra {
+        line: usize,
+        token: String,
+    },
+}
+
+impl fmt::Display for ImportError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ImportError::Io(e) => write!(f, "unable to read passports: {}", e),
+            ImportError::Json { line, error } => write!(f, "line {}: {}", line, error),
+            ImportError::Csv(e) => write!(f, "invalid CSV: {}", e),
+            ImportError::UnexpectedValue { line, key } => {
+                write!(f, "line {}: {} should be a string or null", line, key)
+            }
+            ImportError::MalformedExtra { line, token } => {
+                write!(f, "line {}: {:?} is not a key:value pair", line, token)
+            }
+        }
+    }
+}
+
+impl Error for ImportError {}
+
+impl From<io::Error> for ImportError {
+    fn from(e: io::Error) -> Self {
+        ImportError::Io(e)
+    }
+}
+
+impl From<csv::Error> for ImportError {
+    fn from(e: csv::Error) -> Self {
+        ImportError::Csv(e)
+    }
+}
+
+// Builds a record from imported fields, as if it had been written on one
+// line of a batch file.
+fn imported_record(fields: Vec<(String, String)>, line: usize) -> Record {
+    let text = fields
+        .iter()
+        .map(|(key, value)| format!("{}:{}", key, value))
+        .collect::<Vec<String>>()
+        .join(" ");
+    Record {
+        lines: line..=line,
+        text: Vec::from([text]),
+        fields: fields
+            .into_iter()
+            .map(|(key, value)| RecordField { key, value, line })
+            .collect(),
+    }
+}
+
+// Reads back what export_records wrote. Line numbers in the records are the
+// lines of the export.
+pub fn import_records<R: BufRead>(
+    reader: R,
+    format: ExportFormat,
+) -> Result<Vec<Record>, ImportError> {
+    match format {
+        ExportFormat::JsonLines => import_json_lines(reader),
+        ExportFormat::Csv => import_csv(reader),
+    }
+}
+
+fn import_json_lines<R: BufRead>(reader: R) -> Result<Vec<Record>, ImportError> {
+    let mut records = Vec::new();
+    for (l, line) in reader.lines().zip(1..) {
+        let l = l?;
+        if l.trim().is_empty() {
+            continue;
+        }
+        let object: Map<String, Value> =
+            serde_json::from_str(&l).map_err(|error| ImportError::Json { line, error })?;
+        let mut fields = Vec::with_capacity(object.len());
+        let mut extra = Map::new();
+        for (key, value) in object {
+            match value {
+                Value::Bool(_) if key == VALID_COLUMN => (),
+                Value::Object(object) if key == EXTRA_COLUMN => extra = object,
+                Value::Null => (),
+                Value::String(value) => fields.push((key, value)),
+                _ => return Err(ImportError::UnexpectedValue { line, key }),
+            }
+        }
+        for (key, value) in extra {
+            match value {
+                Value::String(value) => fields.push((key, value)),
+                _ => return Err(ImportError::UnexpectedValue { line, key }),
+            }
+        }
+        records.push(imported_record(fields, line));
+    }
+    Ok(records)
+}
+
+fn import_csv<R: Read>(reader: R) -> Result<Vec<Record>, ImportError> {
+    let mut csv = csv::Reader::from_reader(reader);
+    let header = csv.headers()?.clone();
+    let mut records = Vec::new();
+    for row in csv.records() {
+        let row = row?;
+        let line = row.position().map_or(0, |p| p.line() as usize);
+        let mut fields = Vec::with_capacity(row.len());
+        for (key, value) in header.iter().zip(row.iter()) {
+            match key {
+                VALID_COLUMN => (),
+                EXTRA_COLUMN => {
+                    for token in value.split_whitespace() {
+                        let (key, value) =
+                            token
+                                .split_once(':')
+                                .ok_or_else(|| ImportError::MalformedExtra {
+                                    line,
+                                    token: token.to_string(),
+                                })?;
+                        fields.push((key.to_string(), value.to_string()));
+                    }
+                }
+                _ if value.is_empty() => (),
+                _ => fields.push((key.to_string(), value.to_string())),
+            }
+        }
+        records.push(imported_record(fields, line));
+    }
+    Ok(records)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::day_4::{parse_records, read_records};
+    use std::io::Cursor;
+
+    fn example_records() -> Vec<Record> {
+        let input = "ecl:gry pid:860033327 eyr:2020 hcl:#fffffd\n\
+                     byr:1937 iyr:2017 cid:147 hgt:183cm\n\
+                     \n\
+                     iyr:2013 ecl:amb cid:350 eyr:2023 pid:028048884 pet:\"cat,dog\"\n\
+                     hcl:#cfa07d byr:1929\n";
+        parse_records(Cursor::new(input))
+            .expect("Reading never fails")
+            .records
+    }
+
+    // A passport with its own fields named like the export's columns.
+    fn reserved_record() -> Record {
+        let text = "valid:yes extra:no:really byr:1990".to_string();
+        Record::from_lines(Vec::from([text]), 1, &mut Vec::new())
+    }
+
+    // A passport with fields that are there but have no value.
+    fn empty_values_record() -> Record {
+        let text = "hgt: byr:1990 pet:".to_string();
+        Record::from_lines(Vec::from([text]), 1, &mut Vec::new())
+    }
+
+    fn export(records: &[Record], format: ExportFormat, selection: Selection) -> String {
+        let mut output = Vec::new();
+        export_records(
+            &mut output,
+            records,
+            &Schema::puzzle(),
+            CountType::KeysAndValues,
+            format,
+            selection,
+        )
+        .expect("Writing to a Vec never fails");
+        String::from_utf8(output).expect("Exports are UTF-8")
+    }
+
+    #[test]
+    fn test_export_json_lines() {
+        let output = export(&example_records(), ExportFormat::JsonLines, Selection::All);
+        let lines = output.lines().collect::<Vec<&str>>();
+        assert_eq!(
+            lines,
+            [
+                r##"{"byr":"1937","iyr":"2017","eyr":"2020","hgt":"183cm","hcl":"#fffffd","ecl":"gry","pid":"860033327","cid":"147","extra":{},"valid":true}"##,
+                r##"{"byr":"1929","iyr":"2013","eyr":"2023","hgt":null,"hcl":"#cfa07d","ecl":"amb","pid":"028048884","cid":"350","extra":{"pet":"\"cat,dog\""},"valid":false}"##,
+            ]
+        );
+    }
+
+    #[test]
+    fn test_export_csv_with_selection() {
+        let records = example_records();
+        assert_eq!(
+            export(&records, ExportFormat::Csv, Selection::Invalid),
+            "byr,iyr,eyr,hgt,hcl,ecl,pid,cid,extra,valid\n\
+             1929,2013,2023,,#cfa07d,amb,028048884,350,\"pet:\"\"cat,dog\"\"\",false\n"
+        );
+        let valid = export(&records, ExportFormat::Csv, Selection::Valid);
+        assert_eq!(valid.lines().count(), 2);
+        assert!(valid.ends_with(",147,,true\n"));
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let mut records = read_records("day_4.txt")
+            .expect("Unable to load the file")
+            .records;
+        records.extend(example_records());
+        records.push(reserved_record());
+        records.push(empty_values_record());
+        for format in [ExportFormat::JsonLines, ExportFormat::Csv] {
+            let exported = export(&records, format, Selection::All);
+            let imported =
+                import_records(Cursor::new(exported.as_bytes()), format).expect("Import failed");
+            assert_eq!(imported.len(), records.len());
+            for (original, imported) in records.iter().zip(&imported) {
+                assert_eq!(original.to_map(), imported.to_map());
+            }
+            assert_eq!(export(&imported, format, Selection::All), exported);
+        }
+    }
+
+    #[test]
+    fn test_empty_values_are_not_missing() {
+        let records = Vec::from([empty_values_record()]);
+        assert!(export(&records, ExportFormat::JsonLines, Selection::All)
+            .contains(r#"{"byr":"1990","iyr":null,"eyr":null,"hgt":"","#));
+        assert!(export(&records, ExportFormat::Csv, Selection::All)
+            .ends_with("\n1990,,,,,,,,hgt: pet:,false\n"));
+        for format in [ExportFormat::JsonLines, ExportFormat::Csv] {
+            let exported = export(&records, format, Selection::All);
+            let imported =
+                import_records(Cursor::new(exported.as_bytes()), format).expect("Import failed");
+            let hgt = imported[0].get("hgt").map(|field| field.value.as_str());
+            assert_eq!(hgt, Some(""), "{:?}", format);
+        }
+    }
+
+    #[test]
+    fn test_reserved_fields_are_kept_apart() {
+        let records = Vec::from([reserved_record()]);
+        let json = export(&records, ExportFormat::JsonLines, Selection::All);
+        assert!(json.contains(r#""extra":{"valid":"yes","extra":"no:really"},"valid":false}"#));
+        let csv = export(&records, ExportFormat::Csv, Selection::All);
+        assert!(csv.ends_with(",valid:yes extra:no:really,false\n"));
+        let schema = Schema::from_toml(
+            r#"
+            [[field]]
+            key = "valid"
+            required = true
+            "#,
+        )
+        .expect("Schema should parse");
+        for format in [ExportFormat::JsonLines, ExportFormat::Csv] {
+            let error = export_records(
+                Vec::new(),
+                &records,
+                &schema,
+                CountType::KeysOnly,
+                format,
+                Selection::All,
+            )
+            .expect_err("The schema uses a reserved key");
+            assert_eq!(error.kind(), io::ErrorKind::InvalidInput);
+        }
+    }
+
+    #[test]
+    fn test_import_errors() {
+        let error = import_records(Cursor::new("{\"byr\":1937}\n"), ExportFormat::JsonLines)
+            .expect_err("Numbers are not fields");
+        assert_eq!(error.to_string(), "line 1: byr should be a string or null");
+        let error = import_records(
+            Cursor::new("{\"extra\":{\"pet\":null}}\n"),
+            ExportFormat::JsonLines,
+        )
+        .expect_err("Extra fields are never null");
+        assert_eq!(error.to_string(), "line 1: pet should be a string or null");
+        assert!(matches!(
+            import_records(Cursor::new("\n{\"byr\":\n"), ExportFormat::JsonLines),
+            Err(ImportError::Json { line: 2, .. })
+        ));
+        assert!(matches!(
+            import_records(Cursor::new("byr,extra\n1937,oops\n"), ExportFormat::Csv),
+            Err(ImportError::MalformedExtra { line: 2, .. })
+        ));
+        assert!(matches!(
+            import_records(Cursor::new("byr,extra\n1937\n"), ExportFormat::Csv),
+            Err(ImportError::Csv(_))
+        ));
+    }
+}
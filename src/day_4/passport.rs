@@ -0,0 +1,432 @@
+use super::{puzzle_rule, read_passport_data};
+use std::{collections::HashMap, convert::TryFrom, error::Error, fmt, str::FromStr};
+
+// A value that does not have the shape its field needs, before any range is
+// considered.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct ParseFieldError;
+
+impl fmt::Display for ParseFieldError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "malformed field value")
+    }
+}
+
+impl Error for ParseFieldError {}
+
+fn parse_digits<T: FromStr>(value: &str) -> Result<T, ParseFieldError> {
+    if value.is_empty() || !value.bytes().all(|b| b.is_ascii_digit()) {
+        return Err(ParseFieldError);
+    }
+    value.parse().map_err(|_| ParseFieldError)
+}
+
+// A four digit year.
+#[derive(Clone, Copy, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
+pub struct Year(pub u16);
+
+impl FromStr for Year {
+    type Err = ParseFieldError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if s.len() != 4 {
+            return Err(ParseFieldError);
+        }
+        parse_digits(s).map(Year)
+    }
+}
+
+impl fmt::Display for Year {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{:04}", self.0)
+    }
+}
+
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub enum Height {
+    Centimetres(u32),
+    Inches(u32),
+}
+
+impl Height {
+    // Whether the puzzle schema allows the height: 150 to 193 cm, or 59 to 76
+    // inches.
+    pub fn is_in_range(&self) -> bool {
+        puzzle_rule("hgt").check(&self.to_string())
+    }
+}
+
+impl FromStr for Height {
+    type Err = ParseFieldError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if let Some(cm) = s.strip_suffix("cm") {
+            parse_digits(cm).map(Height::Centimetres)
+        } else if let Some(inches) = s.strip_suffix("in") {
+            parse_digits(inches).map(Height::Inches)
+        } else {
+            Err(ParseFieldError)
+        }
+    }
+}
+
+impl fmt::Display for Height {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Height::Centimetres(cm) => write!(f, "{}cm", cm),
+            Height::Inches(inches) => write!(f, "{}in", inches),
+        }
+    }
+}
+
+// A colour written as # and six lower case hex digits.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub struct HairColour {
+    pub red: u8,
+    pub green: u8,
+    pub blue: u8,
+}
+
+impl FromStr for HairColour {
+    type Err = ParseFieldError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let hex = s.strip_prefix('#').ok_or(ParseFieldError)?;
+        if hex.len() != 6 || !hex.bytes().all(|b| matches!(b, b'0'..=b'9' | b'a'..=b'f')) {
+            return Err(ParseFieldError);
+        }
+        let channel =
+            |i: usize| u8::from_str_radix(&hex[i..i + 2], 16).map_err(|_| ParseFieldError);
+        Ok(HairColour {
+            red: channel(0)?,
+            green: channel(2)?,
+            blue: channel(4)?,
+        })
+    }
+}
+
+impl fmt::Display for HairColour {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "#{:02x}{:02x}{:02x}", self.red, self.green, self.blue)
+    }
+}
+
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub enum EyeColour {
+    Amber,
+    Blue,
+    Brown,
+    Grey,
+    Green,
+    Hazel,
+    Other,
+}
+
+impl FromStr for EyeColour {
+    type Err = ParseFieldError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "amb" => Ok(EyeColour::Amber),
+            "blu" => Ok(EyeColour::Blue),
+            "brn" => Ok(EyeColour::Brown),
+            "gry" => Ok(EyeColour::Grey),
+            "grn" => Ok(EyeColour::Green),
+            "hzl" => Ok(EyeColour::Hazel),
+            "oth" => Ok(EyeColour::Other),
+            _ => Err(ParseFieldError),
+        }
+    }
+}
+
+impl fmt::Display for EyeColour {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let code = match self {
+            EyeColour::Amber => "amb",
+            EyeColour::Blue => "blu",
+            EyeColour::Brown => "brn",
+            EyeColour::Grey => "gry",
+            EyeColour::Green => "grn",
+            EyeColour::Hazel => "hzl",
+            EyeColour::Other => "oth",
+        };
+        write!(f, "{}", code)
+    }
+}
+
+// Nine digits. Kept as text so leading zeros survive.
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+pub struct PassportId(String);
+
+impl PassportId {
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+}
+
+impl FromStr for PassportId {
+    type Err = ParseFieldError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if s.len() == 9 && s.bytes().all(|b| b.is_ascii_digit()) {
+            Ok(PassportId(s.to_string()))
+        } else {
+            Err(ParseFieldError)
+        }
+    }
+}
+
+impl fmt::Display for PassportId {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+// A passport whose fields have all been parsed and checked against the
+// puzzle's rules.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Passport {
+    pub birth_year: Year,
+    pub issue_year: Year,
+    pub expiration_year: Year,
+    pub height: Height,
+    pub hair_colour: HairColour,
+    pub eye_colour: EyeColour,
+    pub passport_id: PassportId,
+    pub country_id: Option<String>,
+}
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum FieldErrorKind {
+    Missing,
+    // The value does not have the right shape, such as "74" for a height.
+    Malformed(String),
+    // The value has the right shape but is outside the allowed range.
+    OutOfRange(String),
+}
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct FieldError {
+    pub key: &'static str,
+    pub kind: FieldErrorKind,
+}
+
+impl fmt::Display for FieldError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match &self.kind {
+            FieldErrorKind::Missing => write!(f, "{} is missing", self.key),
+            FieldErrorKind::Malformed(value) => write!(f, "{} {:?} is malformed", self.key, value),
+            FieldErrorKind::OutOfRange(value) => {
+                write!(f, "{} {:?} is out of range", self.key, value)
+            }
+        }
+    }
+}
+
+// Every field that stopped a record from becoming a Passport, in the order
+// the puzzle lists them.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct PassportError {
+    pub fields: Vec<FieldError>,
+}
+
+impl fmt::Display for PassportError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let fields = self
+            .fields
+            .iter()
+            .map(|e| e.to_string())
+            .collect::<Vec<String>>();
+        write!(f, "invalid passport: {}", fields.join(", "))
+    }
+}
+
+impl Error for PassportError {}
+
+// Parses one field, noting any problem in the error list. The check decides
+// whether a well formed value is in range.
+fn field<T, F>(
+    record: &HashMap<String, String>,
+    key: &'static str,
+    in_range: F,
+    errors: &mut Vec<FieldError>,
+) -> Option<T>
+where
+    T: FromStr,
+    F: Fn(&T) -> bool,
+{
+    let kind = match record.get(key) {
+        None => FieldErrorKind::Missing,
+        Some(value) => match value.parse::<T>() {
+            Ok(parsed) if in_range(&parsed) => return Some(parsed),
+            Ok(_) => FieldErrorKind::OutOfRange(value.clone()),
+            Err(_) => FieldErrorKind::Malformed(value.clone()),
+        },
+    };
+    errors.push(FieldError { key, kind });
+    None
+}
+
+// Checks a year against the puzzle schema's rule for the key.
+fn year_allowed(key: &'static str) -> impl Fn(&Year) -> bool {
+    move |year| puzzle_rule(key).check(&year.to_string())
+}
+
+impl TryFrom<&HashMap<String, String>> for Passport {
+    type Error = PassportError;
+
+    fn try_from(record: &HashMap<String, String>) -> Result<Self, Self::Error> {
+        let mut errors = Vec::new();
+        let birth_year = field(record, "byr", year_allowed("byr"), &mut errors);
+        let issue_year = field(record, "iyr", year_allowed("iyr"), &mut errors);
+        let expiration_year = field(record, "eyr", year_allowed("eyr"), &mut errors);
+        let height = field(record, "hgt", Height::is_in_range, &mut errors);
+        let hair_colour = field(record, "hcl", |_: &HairColour| true, &mut errors);
+        let eye_colour = field(record, "ecl", |_: &EyeColour| true, &mut errors);
+        let passport_id = field(record, "pid", |_: &PassportId| true, &mut errors);
+        match (
+            birth_year,
+            issue_year,
+            expiration_year,
+            height,
+            hair_colour,
+            eye_colour,
+            passport_id,
+        ) {
+            (
+                Some(birth_year),
+                Some(issue_year),
+                Some(expiration_year),
+                Some(height),
+                Some(hair_colour),
+                Some(eye_colour),
+                Some(passport_id),
+            ) => Ok(Passport {
+                birth_year,
+                issue_year,
+                expiration_year,
+                height,
+                hair_colour,
+                eye_colour,
+                passport_id,
+                country_id: record.get("cid").cloned(),
+            }),
+            _ => Err(PassportError { fields: errors }),
+        }
+    }
+}
+
+// Builds a passport straight from the lines of a record.
+impl TryFrom<&[String]> for Passport {
+    type Error = PassportError;
+
+    fn try_from(lines: &[String]) -> Result<Self, Self::Error> {
+        Passport::try_from(&read_passport_data(lines))
+    }
+}
+
+// Every record that makes a valid passport, parsed.
+pub fn parse_passports(passport_data: &[Vec<String>]) -> Vec<Passport> {
+    passport_data
+        .iter()
+        .filter_map(|lines| Passport::try_from(lines.as_slice()).ok())
+        .collect()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::day_4::{count_valid_passports, load_input_file, CountType};
+
+    fn record(fields: &str) -> Vec<String> {
+        Vec::from([fields.to_string()])
+    }
+
+    #[test]
+    fn test_passport_from_record() {
+        let passport = Passport::try_from(
+            record("pid:087499704 hgt:74in ecl:grn iyr:2012 eyr:2030 byr:1980 hcl:#623a2f")
+                .as_slice(),
+        )
+        .expect("Passport should be valid");
+        assert_eq!(
+            passport,
+            Passport {
+                birth_year: Year(1980),
+                issue_year: Year(2012),
+                expiration_year: Year(2030),
+                height: Height::Inches(74),
+                hair_colour: HairColour {
+                    red: 0x62,
+                    green: 0x3a,
+                    blue: 0x2f
+                },
+                eye_colour: EyeColour::Green,
+                passport_id: "087499704".parse().expect("Passport ID should parse"),
+                country_id: None,
+            }
+        );
+        assert_eq!(passport.passport_id.as_str(), "087499704");
+        assert_eq!(passport.hair_colour.to_string(), "#623a2f");
+    }
+
+    #[test]
+    fn test_passport_errors_name_every_field() {
+        let error = Passport::try_from(
+            record("eyr:1972 cid:100 hcl:#18171d ecl:amb hgt:170 pid:186cm byr:1926").as_slice(),
+        )
+        .expect_err("Passport should be invalid");
+        assert_eq!(
+            error.fields,
+            [
+                FieldError {
+                    key: "iyr",
+                    kind: FieldErrorKind::Missing
+                },
+                FieldError {
+                    key: "eyr",
+                    kind: FieldErrorKind::OutOfRange("1972".to_string())
+                },
+                FieldError {
+                    key: "hgt",
+                    kind: FieldErrorKind::Malformed("170".to_string())
+                },
+                FieldError {
+                    key: "pid",
+                    kind: FieldErrorKind::Malformed("186cm".to_string())
+                },
+            ]
+        );
+        assert_eq!(
+            error.to_string(),
+            "invalid passport: iyr is missing, eyr \"1972\" is out of range, \
+             hgt \"170\" is malformed, pid \"186cm\" is malformed"
+        );
+    }
+
+    #[test]
+    fn test_field_parsing() {
+        assert_eq!("2002".parse(), Ok(Year(2002)));
+        assert_eq!("+200".parse::<Year>(), Err(ParseFieldError));
+        assert_eq!("190cm".parse(), Ok(Height::Centimetres(190)));
+        assert_eq!("60in".parse(), Ok(Height::Inches(60)));
+        assert_eq!("in".parse::<Height>(), Err(ParseFieldError));
+        assert!(!Height::Inches(77).is_in_range());
+        assert_eq!("#123abz".parse::<HairColour>(), Err(ParseFieldError));
+        assert_eq!("#123ABC".parse::<HairColour>(), Err(ParseFieldError));
+        assert_eq!("#123abc0".parse::<HairColour>(), Err(ParseFieldError));
+        assert_eq!("hzl".parse(), Ok(EyeColour::Hazel));
+        assert_eq!("wat".parse::<EyeColour>(), Err(ParseFieldError));
+        assert_eq!("0123456789".parse::<PassportId>(), Err(ParseFieldError));
+        assert_eq!("+12345678".parse::<PassportId>(), Err(ParseFieldError));
+    }
+
+    #[test]
+    fn test_parse_passports_matches_count() {
+        let input = load_input_file("day_4.txt").expect("Unable to load the file");
+        assert_eq!(
+            parse_passports(&input).len() as u32,
+            count_valid_passports(&input, CountType::KeysAndValues)
+        );
+    }
+}
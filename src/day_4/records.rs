@@ -0,0 +1,241 @@
+use std::{
+    collections::HashMap,
+    fmt,
+    fs::File,
+    io,
+    io::{BufRead, BufReader},
+    ops::RangeInclusive,
+};
+
+// One key:value pair and the 1-based line it was read from.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct RecordField {
+    pub key: String,
+    pub value: String,
+    pub line: usize,
+}
+
+// A passport as it appears in the input: the lines it spans, their text and
+// the fields read from them. A key given twice keeps its last value, as it
+// did when records were read straight into a HashMap.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Record {
+    pub lines: RangeInclusive<usize>,
+    pub text: Vec<String>,
+    pub fields: Vec<RecordField>,
+}
+
+impl Record {
+    // Reads a record from its lines, the first being on the given line of the
+    // input. Anything skipped is added to the warnings.
+    pub fn from_lines(
+        text: Vec<String>,
+        first_line: usize,
+        warnings: &mut Vec<RecordWarning>,
+    ) -> Record {
+        let mut fields: Vec<RecordField> = Vec::new();
+        for (line, token) in text
+            .iter()
+            .zip(first_line..)
+            .flat_map(|(t, line)| t.split_whitespace().map(move |token| (line, token)))
+        {
+            let kind = match token.split_once(':') {
+                None => WarningKind::MissingColon(token.to_string()),
+                Some(("", _)) => WarningKind::EmptyKey(token.to_string()),
+                Some((key, value)) => match fields.iter_mut().find(|f| f.key == key) {
+                    Some(earlier) => {
+                        let earlier_line = earlier.line;
+                        earlier.value = value.to_string();
+                        earlier.line = line;
+                        WarningKind::DuplicateKey {
+                            key: key.to_string(),
+                            earlier_line,
+                        }
+                    }
+                    None => {
+                        fields.push(RecordField {
+                            key: key.to_string(),
+                            value: value.to_string(),
+                            line,
+                        });
+                        continue;
+                    }
+                },
+            };
+            warnings.push(RecordWarning { line, kind });
+        }
+        Record {
+            lines: first_line..=first_line + text.len().saturating_sub(1),
+            text,
+            fields,
+        }
+    }
+
+    pub fn get(&self, key: &str) -> Option<&RecordField> {
+        self.fields.iter().find(|field| field.key == key)
+    }
+
+    pub fn to_map(&self) -> HashMap<String, String> {
+        self.fields
+            .iter()
+            .map(|field| (field.key.clone(), field.value.clone()))
+            .collect()
+    }
+}
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum WarningKind {
+    // A token with no colon, such as "hgt183cm".
+    MissingColon(String),
+    // A token starting with a colon, such as ":183cm".
+    EmptyKey(String),
+    // A key already given earlier in the same record, on the given line. The
+    // later value replaces the earlier one.
+    DuplicateKey { key: String, earlier_line: usize },
+}
+
+// Part of the input that was skipped, with the 1-based line it is on.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct RecordWarning {
+    pub line: usize,
+    pub kind: WarningKind,
+}
+
+impl fmt::Display for RecordWarning {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "line {}: ", self.line)?;
+        match &self.kind {
+            WarningKind::MissingColon(token) => write!(f, "{:?} has no colon", token),
+            WarningKind::EmptyKey(token) => write!(f, "{:?} has no key", token),
+            WarningKind::DuplicateKey { key, earlier_line } => write!(
+                f,
+                "{} was already given on line {}, replacing the earlier value",
+                key, earlier_line
+            ),
+        }
+    }
+}
+
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct ParsedRecords {
+    pub records: Vec<Record>,
+    pub warnings: Vec<RecordWarning>,
+}
+
+// Splits the input into records at blank lines. Lines holding only whitespace
+// count as blank, and runs of them never produce an empty record.
+pub fn parse_records<R: BufRead>(reader: R) -> io::Result<ParsedRecords> {
+    let mut parsed = ParsedRecords::default();
+    let mut text = Vec::new();
+    let mut first_line = 1;
+    for (l, line) in reader.lines().zip(1..) {
+        let l = l?;
+        if !l.trim().is_empty() {
+            if text.is_empty() {
+                first_line = line;
+            }
+            text.push(l);
+        } else if !text.is_empty() {
+            let record = Record::from_lines(text, first_line, &mut parsed.warnings);
+            parsed.records.push(record);
+            text = Vec::new();
+        }
+    }
+    if !text.is_empty() {
+        let record = Record::from_lines(text, first_line, &mut parsed.warnings);
+        parsed.records.push(record);
+    }
+    Ok(parsed)
+}
+
+pub fn read_records(file_name: &str) -> io::Result<ParsedRecords> {
+    parse_records(BufReader::new(File::open(file_name)?))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use std::io::{Cursor, Read};
+
+    fn field(key: &str, value: &str, line: usize) -> RecordField {
+        RecordField {
+            key: key.to_string(),
+            value: value.to_string(),
+            line,
+        }
+    }
+
+    #[test]
+    fn test_blank_lines_separate_records() {
+        let input = "\n  \nbyr:1937 iyr:2017\r\ncid:147\n\n\t\n\nhgt:183cm\n \n";
+        let parsed = parse_records(Cursor::new(input)).expect("Reading never fails");
+        assert_eq!(
+            parsed.records,
+            [
+                Record {
+                    lines: 3..=4,
+                    text: Vec::from(["byr:1937 iyr:2017".to_string(), "cid:147".to_string()]),
+                    fields: Vec::from([
+                        field("byr", "1937", 3),
+                        field("iyr", "2017", 3),
+                        field("cid", "147", 4)
+                    ]),
+                },
+                Record {
+                    lines: 8..=8,
+                    text: Vec::from(["hgt:183cm".to_string()]),
+                    fields: Vec::from([field("hgt", "183cm", 8)]),
+                },
+            ]
+        );
+        assert!(parsed.warnings.is_empty());
+        assert_eq!(
+            parse_records(Cursor::new("")).expect("Reading never fails"),
+            ParsedRecords::default()
+        );
+    }
+
+    #[test]
+    fn test_warnings() {
+        let input = "ecl:gry hgt183cm\n:147 hgt:150cm\necl:blu pid:1:2\necl:amb\n";
+        let parsed = parse_records(Cursor::new(input)).expect("Reading never fails");
+        let record = &parsed.records[0];
+        assert_eq!(record.get("ecl"), Some(&field("ecl", "amb", 4)));
+        assert_eq!(record.get("pid"), Some(&field("pid", "1:2", 3)));
+        assert_eq!(record.to_map().len(), 3);
+        let warnings = parsed
+            .warnings
+            .iter()
+            .map(|w| w.to_string())
+            .collect::<Vec<String>>();
+        assert_eq!(
+            warnings,
+            [
+                "line 1: \"hgt183cm\" has no colon",
+                "line 2: \":147\" has no key",
+                "line 3: ecl was already given on line 1, replacing the earlier value",
+                "line 4: ecl was already given on line 3, replacing the earlier value",
+            ]
+        );
+    }
+
+    // Fails after giving back some of the input.
+    struct FailingReader(Cursor<&'static str>);
+
+    impl Read for FailingReader {
+        fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+            match self.0.read(buf)? {
+                0 => Err(io::Error::other("disk on fire")),
+                n => Ok(n),
+            }
+        }
+    }
+
+    #[test]
+    fn test_io_errors_are_returned() {
+        let reader = BufReader::new(FailingReader(Cursor::new("byr:1937\n")));
+        let error = parse_records(reader).expect_err("Reading should fail");
+        assert_eq!(error.to_string(), "disk on fire");
+        assert!(read_records("missing_day_4.txt").is_err());
+    }
+}
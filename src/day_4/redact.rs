@@ -0,0 +1,245 @@
+use super::{valid_birth_year, valid_eye_colour, valid_hair_colour, Record, RecordWarning};
+use crate::random::Lcg;
+use std::{io, io::Write, ops::RangeInclusive};
+
+const EYE_COLOURS: [&str; 7] = ["amb", "blu", "brn", "gry", "grn", "hzl", "oth"];
+
+// Swaps personal values in passport records for made up ones of the same
+// shape. Every valid value is replaced with another valid one and every
+// invalid value with another invalid one, so redacted records count the same.
+// The same seed always gives the same output.
+pub struct Redactor {
+    rng: Lcg,
+}
+
+impl Redactor {
+    pub fn new(seed: u64) -> Redactor {
+        Redactor {
+            rng: Lcg::new(seed),
+        }
+    }
+
+    fn between(&mut self, range: RangeInclusive<u8>) -> char {
+        let span = (range.end() - range.start()) as u64 + 1;
+        (range.start() + self.rng.below(span) as u8) as char
+    }
+
+    // Replaces each digit with a digit and each letter with a letter of the
+    // same case, lower case letters coming from the given range. Everything
+    // else is kept.
+    fn scramble(&mut self, value: &str, lower: RangeInclusive<u8>) -> String {
+        value
+            .chars()
+            .map(|c| match c {
+                '0'..='9' => self.between(b'0'..=b'9'),
+                'a'..='z' => self.between(lower.clone()),
+                'A'..='Z' => self.between(b'A'..=b'Z'),
+                _ => c,
+            })
+            .collect()
+    }
+
+    fn redact_value(&mut self, key: &str, value: &str) -> Option<String> {
+        let redacted = match key {
+            // Only the length and whether every character is a digit decide
+            // if a passport ID is valid, and a country ID is never checked.
+            "pid" | "cid" => self.scramble(value, b'a'..=b'z'),
+            "byr" if valid_birth_year(value) => {
+                // Tries four digit years until the schema allows one.
+                loop {
+                    let year = (1000 + self.rng.below(9000)).to_string();
+                    if valid_birth_year(&year) {
+                        break year;
+                    }
+                }
+            }
+            "byr" => {
+                let year = self.scramble(value, b'a'..=b'z');
+                if valid_birth_year(&year) {
+                    value.replace(|c: char| c.is_ascii_digit(), "9")
+                } else {
+                    year
+                }
+            }
+            "hcl" if valid_hair_colour(value) => {
+                format!("#{}", self.rng.string(6, "0123456789abcdef"))
+            }
+            // Lower case letters past f are never hex digits, so an invalid
+            // colour stays invalid.
+            "hcl" => self.scramble(value, b'g'..=b'z'),
+            "ecl" if valid_eye_colour(value) => {
+                EYE_COLOURS[self.rng.below(EYE_COLOURS.len() as u64) as usize].to_string()
+            }
+            "ecl" => {
+                let colour = self.scramble(value, b'a'..=b'z');
+                if valid_eye_colour(&colour) {
+                    value.replace(|c: char| c.is_ascii_alphabetic(), "x")
+                } else {
+                    colour
+                }
+            }
+            _ => return None,
+        };
+        Some(redacted)
+    }
+
+    // Redacts a line of key:value tokens, keeping the spacing between them.
+    pub fn redact_line(&mut self, line: &str) -> String {
+        let mut redacted = String::with_capacity(line.len());
+        let mut end = 0;
+        for token in line.split_whitespace() {
+            let start = token.as_ptr() as usize - line.as_ptr() as usize;
+            redacted.push_str(&line[end..start]);
+            end = start + token.len();
+            match token
+                .split_once(':')
+                .and_then(|(key, value)| Some((key, self.redact_value(key, value)?)))
+            {
+                Some((key, value)) => {
+                    redacted.push_str(key);
+                    redacted.push(':');
+                    redacted.push_str(&value);
+                }
+                None => redacted.push_str(token),
+            }
+        }
+        redacted.push_str(&line[end..]);
+        redacted
+    }
+
+    // Redacts every line of the record, including values that were replaced
+    // by later duplicates, and reads the fields again.
+    pub fn redact(&mut self, record: &Record) -> Record {
+        let text = record
+            .text
+            .iter()
+            .map(|line| self.redact_line(line))
+            .collect();
+        // The record was read from the same tokens, so any warnings are
+        // already known.
+        let mut warnings: Vec<RecordWarning> = Vec::new();
+        Record::from_lines(text, *record.lines.start(), &mut warnings)
+    }
+}
+
+pub fn redact_records(records: &[Record], seed: u64) -> Vec<Record> {
+    let mut redactor = Redactor::new(seed);
+    records
+        .iter()
+        .map(|record| redactor.redact(record))
+        .collect()
+}
+
+// Writes records back out in the puzzle's format, separated by blank lines.
+pub fn write_records<W: Write>(mut writer: W, records: &[Record]) -> io::Result<()> {
+    for (index, record) in records.iter().enumerate() {
+        if index > 0 {
+            writeln!(writer)?;
+        }
+        for line in &record.text {
+            writeln!(writer, "{}", line)?;
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::day_4::{
+        count_valid_passports, count_valid_passports_with_schema, load_input_file, parse_records,
+        read_records, CountType, Schema,
+    };
+    use std::io::Cursor;
+
+    fn redacted_input(file_name: &str, seed: u64) -> (Vec<Record>, Vec<Vec<String>>) {
+        let records = read_records(file_name)
+            .expect("Unable to load the file")
+            .records;
+        let mut output = Vec::new();
+        write_records(&mut output, &redact_records(&records, seed))
+            .expect("Writing to a Vec never fails");
+        let redacted = parse_records(Cursor::new(output))
+            .expect("Reading never fails")
+            .records
+            .into_iter()
+            .map(|record| record.text)
+            .collect();
+        (records, redacted)
+    }
+
+    #[test]
+    fn test_redacted_counts_match() {
+        let original = load_input_file("day_4.txt").expect("Unable to load the file");
+        let schema = Schema::puzzle();
+        for seed in [1, 2020, 0xdead_beef] {
+            let (_, redacted) = redacted_input("day_4.txt", seed);
+            assert_eq!(redacted.len(), original.len());
+            for count_type in [CountType::KeysOnly, CountType::KeysAndValues] {
+                assert_eq!(
+                    count_valid_passports(&redacted, count_type),
+                    count_valid_passports(&original, count_type)
+                );
+                assert_eq!(
+                    count_valid_passports_with_schema(&redacted, &schema, count_type),
+                    count_valid_passports_with_schema(&original, &schema, count_type)
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn test_redaction_replaces_values() {
+        let (original, redacted) = redacted_input("day_4.txt", 2020);
+        let mut changed = 0;
+        let mut total = 0;
+        for (original, redacted) in original.iter().zip(&redacted) {
+            let redacted = Record::from_lines(redacted.clone(), 1, &mut Vec::new());
+            for field in &original.fields {
+                let value = &redacted.get(&field.key).expect("Keys are kept").value;
+                assert_eq!(value.len(), field.value.len());
+                if ["pid", "byr", "hcl", "ecl"].contains(&field.key.as_str()) {
+                    total += 1;
+                    changed += (*value != field.value) as usize;
+                } else if field.key != "cid" {
+                    assert_eq!(*value, field.value);
+                }
+            }
+        }
+        // A few values are swapped for themselves by chance.
+        assert!(changed * 10 > total * 8, "{} of {}", changed, total);
+    }
+
+    #[test]
+    fn test_redact_line() {
+        let mut redactor = Redactor::new(7);
+        let line = redactor.redact_line("pid:186cm  hgt:170\tecl:zzz hcl:#123abz byr:2014 iyr");
+        let tokens = line.split_whitespace().collect::<Vec<&str>>();
+        assert_eq!(line.len(), 51);
+        assert!(line.contains("  hgt:170\t"));
+        assert!(tokens[0].starts_with("pid:") && tokens[0].len() == 9);
+        assert_ne!(tokens[2], "ecl:zzz");
+        assert!(!valid_eye_colour(&tokens[2][4..]));
+        assert!(!valid_hair_colour(&tokens[3][4..]));
+        assert!(!valid_birth_year(&tokens[4][4..]));
+        assert_eq!(tokens[5], "iyr");
+        let mut again = Redactor::new(7);
+        assert_eq!(
+            again.redact_line("pid:186cm  hgt:170\tecl:zzz hcl:#123abz byr:2014 iyr"),
+            line
+        );
+    }
+
+    #[test]
+    fn test_duplicates_are_redacted() {
+        let record = Record::from_lines(
+            Vec::from(["pid:000000001 pid:123456789".to_string()]),
+            3,
+            &mut Vec::new(),
+        );
+        let redacted = Redactor::new(1).redact(&record);
+        assert_eq!(redacted.lines, 3..=3);
+        assert!(!redacted.text[0].contains("000000001"));
+        assert!(!redacted.text[0].contains("123456789"));
+    }
+}
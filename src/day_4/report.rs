@@ -0,0 +1,256 @@
+use super::{CountType, Record, Schema, Violation, ViolationKind};
+use std::{collections::HashMap, fmt, ops::RangeInclusive};
+
+// One reason a passport is not valid, with the value and the rule it broke
+// when the field is there but invalid.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Problem {
+    pub key: String,
+    pub kind: ViolationKind,
+    pub value: Option<String>,
+    pub rule: Option<String>,
+}
+
+impl fmt::Display for Problem {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match (&self.value, &self.rule) {
+            (Some(value), Some(rule)) => write!(f, "{} {:?} should be {}", self.key, value, rule),
+            (Some(value), None) => write!(f, "{} {:?} is invalid", self.key, value),
+            _ => write!(f, "{} is missing", self.key),
+        }
+    }
+}
+
+// Everything wrong with one passport. Records are numbered from 1, and lines
+// are the 1-based lines of the input file the record was read from.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct PassportReport {
+    pub record: usize,
+    pub lines: RangeInclusive<usize>,
+    pub problems: Vec<Problem>,
+}
+
+impl PassportReport {
+    pub fn is_valid(&self) -> bool {
+        self.problems.is_empty()
+    }
+}
+
+impl fmt::Display for PassportReport {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "passport {} (lines {}-{})",
+            self.record,
+            self.lines.start(),
+            self.lines.end()
+        )?;
+        if self.is_valid() {
+            return write!(f, " is valid");
+        }
+        let problems = self
+            .problems
+            .iter()
+            .map(|p| p.to_string())
+            .collect::<Vec<String>>();
+        write!(f, ": {}", problems.join("; "))
+    }
+}
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct ValidationReport {
+    pub passports: Vec<PassportReport>,
+}
+
+impl ValidationReport {
+    pub fn valid_count(&self) -> usize {
+        self.passports.iter().filter(|p| p.is_valid()).count()
+    }
+
+    // How often each field was missing or invalid, most common first. Ties
+    // are ordered by key and then missing before invalid.
+    pub fn histogram(&self) -> Vec<(Violation, usize)> {
+        let mut counts: HashMap<Violation, usize> = HashMap::new();
+        for problem in self.passports.iter().flat_map(|p| &p.problems) {
+            let violation = Violation {
+                key: problem.key.clone(),
+                kind: problem.kind,
+            };
+            *counts.entry(violation).or_insert(0) += 1;
+        }
+        let mut histogram = counts.into_iter().collect::<Vec<(Violation, usize)>>();
+        histogram.sort_by(|(a, a_count), (b, b_count)| {
+            b_count
+                .cmp(a_count)
+                .then_with(|| a.key.cmp(&b.key))
+                .then_with(|| a.kind.cmp(&b.kind))
+        });
+        histogram
+    }
+}
+
+// Lists only the passports with problems, then a summary.
+impl fmt::Display for ValidationReport {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for passport in self.passports.iter().filter(|p| !p.is_valid()) {
+            writeln!(f, "{}", passport)?;
+        }
+        writeln!(
+            f,
+            "{} of {} passports are valid",
+            self.valid_count(),
+            self.passports.len()
+        )?;
+        for (violation, count) in self.histogram() {
+            let kind = match violation.kind {
+                ViolationKind::Missing => "missing",
+                ViolationKind::Invalid => "invalid",
+            };
+            writeln!(f, "{:>6} {} {}", count, kind, violation.key)?;
+        }
+        Ok(())
+    }
+}
+
+// Checks every record against the schema.
+pub fn validation_report(
+    records: &[Record],
+    schema: &Schema,
+    count_type: CountType,
+) -> ValidationReport {
+    let mut passports = Vec::with_capacity(records.len());
+    for (index, record) in records.iter().enumerate() {
+        let passport = record.to_map();
+        let problems = schema
+            .violations(&passport, count_type)
+            .into_iter()
+            .map(|Violation { key, kind }| {
+                let (value, rule) = match kind {
+                    ViolationKind::Missing => (None, None),
+                    ViolationKind::Invalid => (
+                        passport.get(&key).cloned(),
+                        schema
+                            .fields
+                            .iter()
+                            .find(|field| field.key == key)
+                            .and_then(|field| field.rule.as_ref())
+                            .map(|rule| rule.to_string()),
+                    ),
+                };
+                Problem {
+                    key,
+                    kind,
+                    value,
+                    rule,
+                }
+            })
+            .collect();
+        passports.push(PassportReport {
+            record: index + 1,
+            lines: record.lines.clone(),
+            problems,
+        });
+    }
+    ValidationReport { passports }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::day_4::{count_valid_passports, load_input_file, parse_records, read_records};
+    use std::io::Cursor;
+
+    fn records(file_name: &str) -> Vec<Record> {
+        read_records(file_name)
+            .expect("Unable to load the file")
+            .records
+    }
+
+    #[test]
+    fn test_report_on_test_file() {
+        let report = validation_report(
+            &records("day_4_test.txt"),
+            &Schema::puzzle(),
+            CountType::KeysAndValues,
+        );
+        assert_eq!(report.passports.len(), 2);
+        assert!(report.passports[0].is_valid());
+        assert_eq!(report.passports[0].lines, 1..=3);
+        let second = &report.passports[1];
+        assert_eq!((second.record, second.lines.clone()), (2, 5..=7));
+        assert_eq!(
+            second.problems[0],
+            Problem {
+                key: "byr".to_string(),
+                kind: ViolationKind::Invalid,
+                value: Some("2014".to_string()),
+                rule: Some("a 4 digit number from 1920 to 2002".to_string()),
+            }
+        );
+        assert_eq!(
+            second.to_string(),
+            "passport 2 (lines 5-7): \
+             byr \"2014\" should be a 4 digit number from 1920 to 2002; \
+             iyr \"1938\" should be a 4 digit number from 2010 to 2020; \
+             hgt \"66cm\" should be 150 to 193cm or 59 to 76in; \
+             ecl is missing; \
+             pid \"70195175\" should be exactly 9 digits"
+        );
+    }
+
+    #[test]
+    fn test_histogram() {
+        let input = parse_records(Cursor::new("byr:1990 hgt:190in\n\n\nhgt:20cm\niyr:2010\n"))
+            .expect("Reading never fails")
+            .records;
+        let schema = Schema::from_toml(
+            r#"
+            [[field]]
+            key = "byr"
+            required = true
+
+            [[field]]
+            key = "hgt"
+            required = true
+            rule = { type = "unit_range", units = [{ unit = "cm", min = 150, max = 193 }] }
+            "#,
+        )
+        .expect("Schema should parse");
+        let report = validation_report(&input, &schema, CountType::KeysAndValues);
+        let histogram = report
+            .histogram()
+            .into_iter()
+            .map(|(v, count)| (v.key, v.kind, count))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            histogram,
+            [
+                ("hgt".to_string(), ViolationKind::Invalid, 2),
+                ("byr".to_string(), ViolationKind::Missing, 1),
+            ]
+        );
+        assert_eq!(report.passports[1].lines, 4..=5);
+        assert_eq!(
+            report.to_string(),
+            "passport 1 (lines 1-1): hgt \"190in\" should be 150 to 193cm\n\
+             passport 2 (lines 4-5): byr is missing; hgt \"20cm\" should be 150 to 193cm\n\
+             0 of 2 passports are valid\n     \
+             2 invalid hgt\n     \
+             1 missing byr\n"
+        );
+    }
+
+    #[test]
+    fn test_report_agrees_with_count() {
+        let input = load_input_file("day_4.txt").expect("Unable to load the file");
+        let records = records("day_4.txt");
+        let schema = Schema::puzzle();
+        for count_type in [CountType::KeysOnly, CountType::KeysAndValues] {
+            let report = validation_report(&records, &schema, count_type);
+            assert_eq!(
+                report.valid_count() as u32,
+                count_valid_passports(&input, count_type)
+            );
+        }
+    }
+}
@@ -0,0 +1,417 @@
+use super::{read_passport_data, CountType};
+use regex::Regex;
+use serde::Deserialize;
+use std::{
+    collections::{HashMap, HashSet},
+    convert::TryInto,
+    error::Error,
+    fmt, fs, io,
+};
+
+// The rules as written in a schema file, before any regex is compiled.
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct SchemaFile {
+    #[serde(default)]
+    field: Vec<FieldSpec>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct FieldSpec {
+    key: String,
+    name: Option<String>,
+    #[serde(default)]
+    required: bool,
+    rule: Option<RuleSpec>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case", deny_unknown_fields)]
+enum RuleSpec {
+    IntRange {
+        min: i64,
+        max: i64,
+        digits: Option<usize>,
+    },
+    UnitRange {
+        units: Vec<UnitRange>,
+    },
+    Regex {
+        pattern: String,
+    },
+    Enum {
+        values: Vec<String>,
+    },
+    Digits {
+        length: usize,
+    },
+}
+
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq)]
+#[serde(deny_unknown_fields)]
+pub struct UnitRange {
+    pub unit: String,
+    pub min: i64,
+    pub max: i64,
+}
+
+// A typed check on a field's value.
+#[derive(Clone, Debug)]
+pub enum Rule {
+    // A whole number from min to max, with exactly that many digits if given.
+    IntRange {
+        min: i64,
+        max: i64,
+        digits: Option<usize>,
+    },
+    // A whole number followed by a unit, in the range for that unit.
+    UnitRange(Vec<UnitRange>),
+    Regex(Regex),
+    Enum(Vec<String>),
+    // Exactly this many decimal digits.
+    Digits(usize),
+}
+
+fn all_digits(value: &str) -> bool {
+    !value.is_empty() && value.bytes().all(|b| b.is_ascii_digit())
+}
+
+fn number_in_range(value: &str, min: i64, max: i64) -> bool {
+    all_digits(value) && value.parse::<i64>().is_ok_and(|n| (min..=max).contains(&n))
+}
+
+impl Rule {
+    pub fn check(&self, value: &str) -> bool {
+        match self {
+            Rule::IntRange { min, max, digits } => {
+                digits.is_none_or(|digits| value.len() == digits)
+                    && number_in_range(value, *min, *max)
+            }
+            Rule::UnitRange(units) => units.iter().any(|range| {
+                value
+                    .strip_suffix(range.unit.as_str())
+                    .is_some_and(|number| number_in_range(number, range.min, range.max))
+            }),
+            Rule::Regex(regex) => regex.is_match(value),
+            Rule::Enum(values) => values.iter().any(|v| v == value),
+            Rule::Digits(length) => value.len() == *length && all_digits(value),
+        }
+    }
+}
+
+// Describes what a value has to look like, for reports.
+impl fmt::Display for Rule {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Rule::IntRange {
+                min,
+                max,
+                digits: Some(digits),
+            } => write!(f, "a {} digit number from {} to {}", digits, min, max),
+            Rule::IntRange { min, max, .. } => write!(f, "a number from {} to {}", min, max),
+            Rule::UnitRange(units) => {
+                let units = units
+                    .iter()
+                    .map(|range| format!("{} to {}{}", range.min, range.max, range.unit))
+                    .collect::<Vec<String>>();
+                write!(f, "{}", units.join(" or "))
+            }
+            Rule::Regex(regex) => write!(f, "text matching {}", regex),
+            Rule::Enum(values) => write!(f, "one of {}", values.join(", ")),
+            Rule::Digits(length) => write!(f, "exactly {} digits", length),
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct Field {
+    pub key: String,
+    pub name: String,
+    pub required: bool,
+    pub rule: Option<Rule>,
+}
+
+#[derive(Clone, Copy, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
+pub enum ViolationKind {
+    Missing,
+    Invalid,
+}
+
+// A field that stops a passport from being valid.
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+pub struct Violation {
+    pub key: String,
+    pub kind: ViolationKind,
+}
+
+#[derive(Debug)]
+pub enum SchemaError {
+    Io(io::Error),
+    Toml(toml::de::Error),
+    Regex { key: String, error: regex::Error },
+    DuplicateField(String),
+}
+
+impl fmt::Display for SchemaError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            SchemaError::Io(e) => write!(f, "unable to read schema: {}", e),
+            SchemaError::Toml(e) => write!(f, "invalid schema: {}", e),
+            SchemaError::Regex { key, error } => {
+                write!(f, "invalid pattern for field {}: {}", key, error)
+            }
+            SchemaError::DuplicateField(key) => write!(f, "field {} is declared twice", key),
+        }
+    }
+}
+
+impl Error for SchemaError {}
+
+impl From<io::Error> for SchemaError {
+    fn from(e: io::Error) -> Self {
+        SchemaError::Io(e)
+    }
+}
+
+// The fields a passport may have and the rules for each, read from a TOML
+// schema. See passport_schema.toml for the format.
+#[derive(Clone, Debug)]
+pub struct Schema {
+    pub fields: Vec<Field>,
+}
+
+const PUZZLE_SCHEMA: &str = include_str!("passport_schema.toml");
+
+impl Schema {
+    pub fn from_toml(schema: &str) -> Result<Schema, SchemaError> {
+        let file: SchemaFile = toml::from_str(schema).map_err(SchemaError::Toml)?;
+        let mut keys = HashSet::new();
+        let mut fields = Vec::with_capacity(file.field.len());
+        for FieldSpec {
+            key,
+            name,
+            required,
+            rule,
+        } in file.field
+        {
+            if !keys.insert(key.clone()) {
+                return Err(SchemaError::DuplicateField(key));
+            }
+            let rule = match rule {
+                None => None,
+                Some(RuleSpec::IntRange { min, max, digits }) => {
+                    Some(Rule::IntRange { min, max, digits })
+                }
+                Some(RuleSpec::UnitRange { units }) => Some(Rule::UnitRange(units)),
+                Some(RuleSpec::Regex { pattern }) => match Regex::new(&pattern) {
+                    Ok(regex) => Some(Rule::Regex(regex)),
+                    Err(error) => return Err(SchemaError::Regex { key, error }),
+                },
+                Some(RuleSpec::Enum { values }) => Some(Rule::Enum(values)),
+                Some(RuleSpec::Digits { length }) => Some(Rule::Digits(length)),
+            };
+            fields.push(Field {
+                name: name.unwrap_or_else(|| key.clone()),
+                key,
+                required,
+                rule,
+            });
+        }
+        Ok(Schema { fields })
+    }
+
+    pub fn load(file_name: &str) -> Result<Schema, SchemaError> {
+        Schema::from_toml(&fs::read_to_string(file_name)?)
+    }
+
+    // The rules from the puzzle text.
+    pub fn puzzle() -> Schema {
+        Schema::from_toml(PUZZLE_SCHEMA).expect("The puzzle schema should be valid")
+    }
+
+    // Every required field that is missing and, when checking values, every
+    // field whose value breaks its rule, in schema order.
+    pub fn violations(
+        &self,
+        passport: &HashMap<String, String>,
+        count_type: CountType,
+    ) -> Vec<Violation> {
+        self.fields
+            .iter()
+            .filter_map(|field| {
+                let kind = match (passport.get(&field.key), &field.rule, count_type) {
+                    (None, _, _) if field.required => ViolationKind::Missing,
+                    (Some(value), Some(rule), CountType::KeysAndValues) if !rule.check(value) => {
+                        ViolationKind::Invalid
+                    }
+                    _ => return None,
+                };
+                Some(Violation {
+                    key: field.key.clone(),
+                    kind,
+                })
+            })
+            .collect()
+    }
+
+    pub fn is_valid(&self, passport: &HashMap<String, String>, count_type: CountType) -> bool {
+        self.violations(passport, count_type).is_empty()
+    }
+}
+
+pub fn count_valid_passports_with_schema(
+    passport_data: &[Vec<String>],
+    schema: &Schema,
+    count_type: CountType,
+) -> u32 {
+    passport_data
+        .iter()
+        .filter(|p| schema.is_valid(&read_passport_data(p), count_type))
+        .count()
+        .try_into()
+        .expect("Can't convert usize to u32")
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn passport(fields: &str) -> HashMap<String, String> {
+        read_passport_data(&[fields.to_string()])
+    }
+
+    #[test]
+    fn test_puzzle_schema_matches_puzzle_examples() {
+        let schema = Schema::puzzle();
+        let valid = [
+            "pid:087499704 hgt:74in ecl:grn iyr:2012 eyr:2030 byr:1980 hcl:#623a2f",
+            "eyr:2029 ecl:blu cid:129 byr:1989 iyr:2014 pid:896056539 hcl:#a97842 hgt:165cm",
+            "hcl:#888785 hgt:164cm byr:2001 iyr:2015 cid:88 pid:545766238 ecl:hzl eyr:2022",
+            "iyr:2010 hgt:158cm hcl:#b6652a ecl:blu byr:1944 eyr:2021 pid:093154719",
+        ];
+        let invalid = [
+            "eyr:1972 cid:100 hcl:#18171d ecl:amb hgt:170 pid:186cm iyr:2018 byr:1926",
+            "iyr:2019 hcl:#602927 eyr:1967 hgt:170cm ecl:grn pid:012533040 byr:1946",
+            "hcl:dab227 iyr:2012 ecl:brn hgt:182cm pid:021572410 eyr:2020 byr:1992 cid:277",
+            "hgt:59cm ecl:zzz eyr:2038 hcl:74454a iyr:2023 pid:3556412378 byr:2007",
+        ];
+        for p in valid.iter() {
+            assert!(
+                schema.is_valid(&passport(p), CountType::KeysAndValues),
+                "{}",
+                p
+            );
+        }
+        for p in invalid.iter() {
+            assert!(
+                !schema.is_valid(&passport(p), CountType::KeysAndValues),
+                "{}",
+                p
+            );
+            assert!(schema.is_valid(&passport(p), CountType::KeysOnly), "{}", p);
+        }
+    }
+
+    #[test]
+    fn test_violations() {
+        let schema = Schema::puzzle();
+        let violations = schema.violations(
+            &passport("hgt:59cm ecl:zzz eyr:2038 hcl:#74454a byr:2002"),
+            CountType::KeysAndValues,
+        );
+        let found = violations
+            .iter()
+            .map(|v| (v.key.as_str(), v.kind))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            found,
+            [
+                ("iyr", ViolationKind::Missing),
+                ("eyr", ViolationKind::Invalid),
+                ("hgt", ViolationKind::Invalid),
+                ("ecl", ViolationKind::Invalid),
+                ("pid", ViolationKind::Missing),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_rules() {
+        let year = Rule::IntRange {
+            min: 1920,
+            max: 2002,
+            digits: Some(4),
+        };
+        assert!(year.check("2002") && year.check("1920"));
+        assert!(!year.check("2003") && !year.check("+2000") && !year.check("02000"));
+        let height = Rule::UnitRange(Vec::from([UnitRange {
+            unit: "cm".to_string(),
+            min: 150,
+            max: 193,
+        }]));
+        assert!(height.check("150cm"));
+        assert!(!height.check("1900cm") && !height.check("cm") && !height.check("150"));
+        let id = Rule::Digits(9);
+        assert!(id.check("000000001"));
+        assert!(!id.check("+12345678") && !id.check("0123456789"));
+    }
+
+    #[test]
+    fn test_custom_schema() {
+        let schema = Schema::from_toml(
+            r#"
+            [[field]]
+            key = "pet"
+            required = true
+            rule = { type = "enum", values = ["cat", "dog"] }
+
+            [[field]]
+            key = "tag"
+            rule = { type = "regex", pattern = "^[A-Z]{2}$" }
+            "#,
+        )
+        .expect("Schema should parse");
+        assert_eq!(schema.fields[0].name, "pet");
+        assert!(schema.is_valid(&passport("pet:cat"), CountType::KeysAndValues));
+        assert!(schema.is_valid(&passport("pet:dog tag:AB"), CountType::KeysAndValues));
+        assert!(!schema.is_valid(&passport("pet:dog tag:abc"), CountType::KeysAndValues));
+        assert!(!schema.is_valid(&passport("tag:AB"), CountType::KeysOnly));
+    }
+
+    #[test]
+    fn test_schema_errors() {
+        let error = |schema| Schema::from_toml(schema).expect_err("Schema should be rejected");
+        assert!(matches!(
+            error("[[field]]\nkey = \"a\"\nrule = { type = \"regex\", pattern = \"(\" }"),
+            SchemaError::Regex { .. }
+        ));
+        assert!(matches!(
+            error("[[field]]\nkey = \"a\"\n[[field]]\nkey = \"a\""),
+            SchemaError::DuplicateField(key) if key == "a"
+        ));
+        assert!(matches!(
+            error("[[field]]\nkey = \"a\"\nrule = { type = \"colour\" }"),
+            SchemaError::Toml(_)
+        ));
+        assert!(matches!(
+            Schema::load("missing_schema.toml"),
+            Err(SchemaError::Io(_))
+        ));
+    }
+
+    #[test]
+    fn test_count_valid_passports_with_schema() {
+        let input =
+            super::super::load_input_file("day_4_test.txt").expect("Unable to load the file");
+        let schema = Schema::puzzle();
+        assert_eq!(
+            count_valid_passports_with_schema(&input, &schema, CountType::KeysOnly),
+            1
+        );
+        assert_eq!(
+            count_valid_passports_with_schema(&input, &schema, CountType::KeysAndValues),
+            1
+        );
+    }
+}
@@ -3,15 +3,15 @@ fn seat_rows() -> Vec<u32> {
 }
 
 fn lower_half(seats: &[u32]) -> Vec<u32> {
-    seats[0..mid_point(&seats)].to_vec()
+    seats[0..mid_point(seats)].to_vec()
 }
 
 fn upper_half(seats: &[u32]) -> Vec<u32> {
-    seats[mid_point(&seats)..seats.len()].to_vec()
+    seats[mid_point(seats)..seats.len()].to_vec()
 }
 
 fn mid_point(seats: &[u32]) -> usize {
-    assert!(seats.len() % 2 == 0);
+    assert!(seats.len().is_multiple_of(2));
     seats.len() / 2
 }
 
@@ -28,7 +28,7 @@ fn find_row(seats: &[u32], input: &[char]) -> Vec<u32> {
         seats.to_vec()
     } else {
         find_row(
-            &split_seats(&seats, *input.first().expect("No character")),
+            &split_seats(seats, *input.first().expect("No character")),
             &input[1..input.len()],
         )
     }
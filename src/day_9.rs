@@ -1,26 +1,118 @@
+use crate::big_int::BigInt;
 use std::{
+    cmp::Ordering,
+    collections::{HashMap, VecDeque},
+    convert::TryFrom,
+    error::Error,
+    fmt::{Debug, Display},
     fs::File,
+    hash::Hash,
     io,
     io::{BufRead, BufReader},
+    ops::Range,
+    str::FromStr,
 };
 
-// Checks to see if all the numbers in the given sequence
-// are valid. When the first invalid number is found, a list is returned with result.
-// The first item in the list tells you if the list is valid, the second gives
-// the invalid number if found. The first invalid number found will stop the search
-// and return the number.
-fn valid_next_number(sequence: &[i64], number: i64) -> bool {
-    for i in sequence {
-        for j in sequence {
-            if (i + j == number) && (i != j) {
-                return true;
+// The numeric types an XMAS sequence can be made of. Arithmetic is checked so
+// that sums near the edge of a fixed-width type are never silently wrapped.
+pub trait XmasNumber: Clone + Debug + Display + Eq + FromStr + Hash + Ord {
+    fn zero() -> Self;
+    fn checked_add(&self, other: &Self) -> Option<Self>;
+    fn checked_sub(&self, other: &Self) -> Option<Self>;
+    fn to_big_int(&self) -> BigInt;
+}
+
+macro_rules! impl_xmas_number {
+    ($($t:ty),*) => {
+        $(
+            impl XmasNumber for $t {
+                fn zero() -> Self {
+                    0
+                }
+
+                fn checked_add(&self, other: &Self) -> Option<Self> {
+                    <$t>::checked_add(*self, *other)
+                }
+
+                fn checked_sub(&self, other: &Self) -> Option<Self> {
+                    <$t>::checked_sub(*self, *other)
+                }
+
+                #[allow(irrefutable_let_patterns)]
+                fn to_big_int(&self) -> BigInt {
+                    if let Ok(value) = i128::try_from(*self) {
+                        return BigInt::from(value);
+                    }
+                    // Only a u128 can be too big for an i128.
+                    let value = *self as u128;
+                    let high = BigInt::from((value >> 64) as i128) * BigInt::from(1i128 << 64);
+                    high + BigInt::from(value as u64 as i128)
+                }
+            }
+        )*
+    };
+}
+
+impl_xmas_number!(i32, i64, i128, u64, u128);
+
+impl XmasNumber for BigInt {
+    fn zero() -> Self {
+        BigInt::zero()
+    }
+
+    fn checked_add(&self, other: &Self) -> Option<Self> {
+        Some(self + other)
+    }
+
+    fn checked_sub(&self, other: &Self) -> Option<Self> {
+        Some(self - other)
+    }
+
+    fn to_big_int(&self) -> BigInt {
+        self.clone()
+    }
+}
+
+// A multiset of the numbers in the current preamble. Keeping counts means a
+// number that appears twice in the window survives one of its copies being
+// removed, and a candidate can be checked against it in a single pass.
+struct Preamble<T> {
+    counts: HashMap<T, usize>,
+}
+
+impl<T: XmasNumber> Preamble<T> {
+    fn new(numbers: &[T]) -> Preamble<T> {
+        let mut preamble = Preamble {
+            counts: HashMap::with_capacity(numbers.len()),
+        };
+        for number in numbers {
+            preamble.insert(number.clone())
+        }
+        preamble
+    }
+
+    fn insert(&mut self, number: T) {
+        *self.counts.entry(number).or_insert(0) += 1
+    }
+
+    fn remove(&mut self, number: &T) {
+        if let Some(count) = self.counts.get_mut(number) {
+            *count -= 1;
+            if *count == 0 {
+                self.counts.remove(number);
             }
         }
     }
-    false
+
+    fn has_pair_summing_to(&self, number: &T) -> bool {
+        self.counts.keys().any(|i| match number.checked_sub(i) {
+            Some(j) => *i != j && self.counts.contains_key(&j),
+            None => false,
+        })
+    }
 }
 
-fn preamble(sequence: &[i64], position: usize, preamble_length: usize) -> Option<&[i64]> {
+fn preamble<T>(sequence: &[T], position: usize, preamble_length: usize) -> Option<&[T]> {
     if position >= sequence.len() || position < preamble_length {
         None
     } else {
@@ -28,69 +120,323 @@ fn preamble(sequence: &[i64], position: usize, preamble_length: usize) -> Option
     }
 }
 
-pub fn all_numbers_valid(sequence: &[i64], preamble_length: usize) -> (bool, Option<i64>) {
-    for number in sequence {
-        if let Some(pos) = position(&sequence, *number) {
-            if let Some(p) = preamble(sequence, pos, preamble_length) {
-                if !(valid_next_number(p, *number)) {
-                    return (false, Some(*number));
-                }
+type Pair<T> = (T, T);
+
+// A number that is not the sum of two numbers in its preamble, along with the
+// preamble it was checked against and the pair sums that came closest to it.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct InvalidNumber<T = i64> {
+    pub index: usize,
+    pub value: T,
+    pub preamble: Vec<T>,
+    pub closest_below: Option<Pair<T>>,
+    pub closest_above: Option<Pair<T>>,
+}
+
+impl<T: XmasNumber> InvalidNumber<T> {
+    fn new(index: usize, value: T, preamble: &[T]) -> InvalidNumber<T> {
+        let (closest_below, closest_above) = nearest_pair_sums(preamble, &value);
+        InvalidNumber {
+            index,
+            value,
+            preamble: preamble.to_vec(),
+            closest_below,
+            closest_above,
+        }
+    }
+}
+
+// Finds the pairs of distinct preamble numbers whose sums fall closest below
+// and closest above the target. Sums that do not fit in the number type are
+// stepped past but never reported.
+fn nearest_pair_sums<T: XmasNumber>(
+    preamble: &[T],
+    target: &T,
+) -> (Option<Pair<T>>, Option<Pair<T>>) {
+    let mut values = preamble.to_vec();
+    values.sort_unstable();
+    values.dedup();
+    let mut below: Option<(T, Pair<T>)> = None;
+    let mut above: Option<(T, Pair<T>)> = None;
+    let (mut low, mut high) = (0, values.len().saturating_sub(1));
+    while low < high {
+        let pair = (values[low].clone(), values[high].clone());
+        let sum = pair.0.checked_add(&pair.1);
+        let ordering = match &sum {
+            Some(sum) => sum.cmp(target),
+            None if pair.0 < T::zero() => Ordering::Less,
+            None => Ordering::Greater,
+        };
+        match (ordering, sum) {
+            (Ordering::Less, Some(sum)) if below.as_ref().is_none_or(|(b, _)| sum > *b) => {
+                below = Some((sum, pair))
+            }
+            (Ordering::Greater, Some(sum)) if above.as_ref().is_none_or(|(a, _)| sum < *a) => {
+                above = Some((sum, pair))
             }
+            _ => (),
+        }
+        if ordering == Ordering::Less {
+            low += 1;
+        } else {
+            high -= 1;
         }
     }
-    (true, None)
+    (below.map(|(_, p)| p), above.map(|(_, p)| p))
 }
 
-fn position(sequence: &[i64], number: i64) -> Option<usize> {
-    for (idx, n) in sequence.iter().enumerate() {
-        if *n == number {
-            return Some(idx);
+#[derive(Debug, Eq, PartialEq)]
+pub struct ValidationReport<T = i64> {
+    pub invalid: Vec<InvalidNumber<T>>,
+}
+
+impl<T> Default for ValidationReport<T> {
+    fn default() -> Self {
+        ValidationReport {
+            invalid: Vec::new(),
         }
     }
-    None
 }
 
-fn find_sequence_that_sums_target(sequence: &[i64], target: i64) -> Option<Vec<i64>> {
-    let mut subsequence = Vec::new();
-    let mut current_total: i64;
-    for number in sequence {
-        subsequence.push(*number);
-        current_total = subsequence.iter().sum();
-        if current_total == target && subsequence.len() > 2 {
-            return Some(subsequence);
-        } else if current_total > target {
-            return find_sequence_that_sums_target(sequence.split_first().unwrap().1, target);
+impl<T> ValidationReport<T> {
+    pub fn is_valid(&self) -> bool {
+        self.invalid.is_empty()
+    }
+
+    pub fn first_invalid(&self) -> Option<&InvalidNumber<T>> {
+        self.invalid.first()
+    }
+}
+
+// Checks every number in the given sequence against its preamble and reports
+// each one that is invalid. The preamble is slid along the sequence by index,
+// so repeated values are checked against their own window.
+pub fn all_numbers_valid<T: XmasNumber>(
+    sequence: &[T],
+    preamble_length: usize,
+) -> ValidationReport<T> {
+    let mut report = ValidationReport::default();
+    let mut window = match preamble(sequence, preamble_length, preamble_length) {
+        Some(p) => Preamble::new(p),
+        None => return report,
+    };
+    for idx in preamble_length..sequence.len() {
+        let number = &sequence[idx];
+        if !window.has_pair_summing_to(number) {
+            if let Some(p) = preamble(sequence, idx, preamble_length) {
+                report
+                    .invalid
+                    .push(InvalidNumber::new(idx, number.clone(), p))
+            }
+        }
+        // An empty preamble stays empty rather than sliding.
+        if preamble_length > 0 {
+            window.remove(&sequence[idx - preamble_length]);
+            window.insert(number.clone());
         }
     }
-    None
+    report
 }
 
-pub fn encryption_weakness(sequence: &[i64], target: i64) -> Option<i64> {
-    match find_sequence_that_sums_target(sequence, target) {
-        Some(s) => match s.iter().min() {
-            Some(x) => match s.iter().max() {
-                Some(y) => Some(x + y),
-                None => None,
-            },
-            None => None,
-        },
-        None => None,
+// Validates a stream of numbers one at a time. Only the last preamble_length
+// numbers are kept, so the input never needs to be held in memory.
+pub struct XmasValidator<T = i64> {
+    preamble_length: usize,
+    window: VecDeque<T>,
+    preamble: Preamble<T>,
+    index: usize,
+}
+
+impl<T: XmasNumber> XmasValidator<T> {
+    pub fn new(preamble_length: usize) -> XmasValidator<T> {
+        XmasValidator {
+            preamble_length,
+            window: VecDeque::with_capacity(preamble_length),
+            preamble: Preamble::new(&[]),
+            index: 0,
+        }
+    }
+
+    // Feeds the next number through the validator, returning a report of it
+    // if it is not the sum of two numbers in the preamble. Numbers that make
+    // up the first preamble are never reported.
+    pub fn next_number(&mut self, number: T) -> Option<InvalidNumber<T>> {
+        let index = self.index;
+        self.index += 1;
+        let invalid = if self.window.len() == self.preamble_length
+            && !self.preamble.has_pair_summing_to(&number)
+        {
+            Some(InvalidNumber::new(
+                index,
+                number.clone(),
+                self.window.make_contiguous(),
+            ))
+        } else {
+            None
+        };
+        if self.window.len() == self.preamble_length {
+            if let Some(oldest) = self.window.pop_front() {
+                self.preamble.remove(&oldest);
+            }
+        }
+        if self.preamble_length > 0 {
+            self.window.push_back(number.clone());
+            self.preamble.insert(number);
+        }
+        invalid
     }
 }
 
-pub fn load_input_file(file_name: &str) -> io::Result<Vec<i64>> {
-    let input = File::open(file_name)?;
-    let reader = BufReader::new(input);
-    let mut numbers = Vec::new();
+// Lazily yields every invalid number in the input.
+pub fn invalid_numbers<T, I>(
+    numbers: I,
+    preamble_length: usize,
+) -> impl Iterator<Item = InvalidNumber<T>>
+where
+    T: XmasNumber,
+    I: IntoIterator<Item = T>,
+{
+    let mut validator = XmasValidator::new(preamble_length);
+    numbers
+        .into_iter()
+        .filter_map(move |number| validator.next_number(number))
+}
 
-    for line in reader.lines() {
-        let nmb = line
-            .expect("Gap in input list")
-            .parse::<i64>()
-            .expect("Problems parsing");
-        numbers.push(nmb)
+// As invalid_numbers, reading one number per line. Read and parse errors are
+// passed through in place.
+pub fn invalid_numbers_in_reader<T, R>(
+    reader: R,
+    preamble_length: usize,
+) -> impl Iterator<Item = io::Result<InvalidNumber<T>>>
+where
+    T: XmasNumber,
+    T::Err: Error + Send + Sync + 'static,
+    R: BufRead,
+{
+    let mut validator = XmasValidator::new(preamble_length);
+    read_numbers(reader).filter_map(move |number| match number {
+        Ok(n) => validator.next_number(n).map(Ok),
+        Err(e) => Some(Err(e)),
+    })
+}
+
+// Parses one number per line, skipping blank lines.
+pub fn read_numbers<T, R>(reader: R) -> impl Iterator<Item = io::Result<T>>
+where
+    T: FromStr,
+    T::Err: Error + Send + Sync + 'static,
+    R: BufRead,
+{
+    reader.lines().filter_map(|line| match line {
+        Ok(l) if l.trim().is_empty() => None,
+        Ok(l) => Some(
+            l.trim()
+                .parse::<T>()
+                .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e)),
+        ),
+        Err(e) => Some(Err(e)),
+    })
+}
+
+#[derive(Debug, Eq, PartialEq)]
+pub enum WeaknessError {
+    NoRange,
+    Overflow,
+}
+
+// The shortest contiguous run that the encryption weakness can be made of.
+pub const MIN_WEAKNESS_LENGTH: usize = 2;
+
+// Finds a contiguous range of at least min_length numbers that sums to the
+// target, returning its index range. Non-negative sequences are searched with
+// a sliding window; anything with a negative number falls back to prefix sums.
+pub fn find_sequence_that_sums_target<T: XmasNumber>(
+    sequence: &[T],
+    target: &T,
+    min_length: usize,
+) -> Result<Range<usize>, WeaknessError> {
+    let min_length = min_length.max(1);
+    if sequence.iter().all(|n| *n >= T::zero()) {
+        sliding_window_sum(sequence, target, min_length)
+    } else {
+        prefix_sum_search(sequence, target, min_length)
+    }
+}
+
+// With no negative numbers the window only grows its sum to the right and
+// shrinks it from the left, so the start is always the earliest index that
+// keeps the sum at or below the target. A sum too big for the type is
+// necessarily over the target, so it just shrinks the window.
+fn sliding_window_sum<T: XmasNumber>(
+    sequence: &[T],
+    target: &T,
+    min_length: usize,
+) -> Result<Range<usize>, WeaknessError> {
+    let mut start = 0;
+    let mut total = T::zero();
+    for (end, number) in sequence.iter().enumerate() {
+        while start < end && total.checked_add(number).is_none_or(|t| t > *target) {
+            total = total
+                .checked_sub(&sequence[start])
+                .ok_or(WeaknessError::Overflow)?;
+            start += 1;
+        }
+        total = total.checked_add(number).ok_or(WeaknessError::Overflow)?;
+        if total == *target && end + 1 - start >= min_length {
+            return Ok(start..end + 1);
+        }
     }
-    Ok(numbers)
+    Err(WeaknessError::NoRange)
+}
+
+// Looks for an earlier prefix sum that differs from the current one by the
+// target. Prefix sums only become candidates once they are far enough behind
+// to give a run of min_length. If a prefix sum is too big for the type, a run
+// summing to the target may still fit, so the search starts again with big
+// integers.
+fn prefix_sum_search<T: XmasNumber>(
+    sequence: &[T],
+    target: &T,
+    min_length: usize,
+) -> Result<Range<usize>, WeaknessError> {
+    let mut prefixes = vec![T::zero()];
+    let mut earliest: HashMap<T, usize> = HashMap::new();
+    for (end, number) in sequence.iter().enumerate() {
+        let total = match prefixes[end].checked_add(number) {
+            Some(total) => total,
+            None => {
+                let wide = sequence.iter().map(T::to_big_int).collect::<Vec<BigInt>>();
+                return prefix_sum_search(&wide, &target.to_big_int(), min_length);
+            }
+        };
+        prefixes.push(total.clone());
+        let exclusive_end = end + 1;
+        if exclusive_end >= min_length {
+            let start = exclusive_end - min_length;
+            earliest.entry(prefixes[start].clone()).or_insert(start);
+        }
+        if let Some(start) = total.checked_sub(target).and_then(|t| earliest.get(&t)) {
+            return Ok(*start..exclusive_end);
+        }
+    }
+    Err(WeaknessError::NoRange)
+}
+
+pub fn encryption_weakness<T: XmasNumber>(sequence: &[T], target: &T) -> Result<T, WeaknessError> {
+    let range = find_sequence_that_sums_target(sequence, target, MIN_WEAKNESS_LENGTH)?;
+    let run = &sequence[range];
+    let min = run.iter().min().ok_or(WeaknessError::NoRange)?;
+    let max = run.iter().max().ok_or(WeaknessError::NoRange)?;
+    min.checked_add(max).ok_or(WeaknessError::Overflow)
+}
+
+pub fn load_input_file<T>(file_name: &str) -> io::Result<Vec<T>>
+where
+    T: FromStr,
+    T::Err: Error + Send + Sync + 'static,
+{
+    let input = File::open(file_name)?;
+    read_numbers(BufReader::new(input)).collect()
 }
 
 #[cfg(test)]
@@ -98,10 +444,11 @@ mod test {
     use super::*;
 
     #[test]
-    fn test_valid_next_number() {
+    fn test_preamble_has_pair_summing_to() {
         let sequence = (1..25).map(|i| i as i64).collect::<Vec<i64>>();
-        assert_eq!(valid_next_number(&sequence, 25), true);
-        assert_eq!(valid_next_number(&sequence, 50), false)
+        let preamble = Preamble::new(&sequence);
+        assert!(preamble.has_pair_summing_to(&25));
+        assert!(!preamble.has_pair_summing_to(&50))
     }
 
     #[test]
@@ -119,7 +466,59 @@ mod test {
             35, 20, 15, 25, 47, 40, 62, 55, 65, 95, 102, 117, 150, 182, 127, 219, 299, 277, 309,
             576,
         ];
-        assert_eq!(all_numbers_valid(&sequence, 5), (false, Some(127)))
+        let report = all_numbers_valid(&sequence, 5);
+        assert!(!report.is_valid());
+        assert_eq!(
+            report.invalid,
+            vec![InvalidNumber {
+                index: 14,
+                value: 127,
+                preamble: vec![95, 102, 117, 150, 182],
+                closest_below: None,
+                closest_above: Some((95, 102)),
+            }]
+        )
+    }
+
+    #[test]
+    fn test_all_numbers_valid_reports_every_invalid_number() {
+        let sequence = [1, 2, 3, 5, 8, 3, 30, 33];
+        let report = all_numbers_valid(&sequence, 2);
+        let invalid = report
+            .invalid
+            .iter()
+            .map(|i| (i.index, i.value))
+            .collect::<Vec<_>>();
+        assert_eq!(invalid, vec![(5, 3), (6, 30)]);
+        assert_eq!(report.first_invalid().map(|i| i.value), Some(3))
+    }
+
+    #[test]
+    fn test_nearest_pair_sums() {
+        let preamble = [1, 4, 10, 20];
+        assert_eq!(
+            nearest_pair_sums(&preamble, &13),
+            (Some((1, 10)), Some((4, 10)))
+        );
+        assert_eq!(nearest_pair_sums(&preamble, &100), (Some((10, 20)), None));
+        assert_eq!(nearest_pair_sums(&preamble, &2), (None, Some((1, 4))));
+        assert_eq!(nearest_pair_sums(&[5, 5], &10), (None, None))
+    }
+
+    #[test]
+    fn test_all_numbers_valid_with_repeated_number() {
+        let sequence = [1, 2, 3, 5, 8, 3];
+        let report = all_numbers_valid(&sequence, 2);
+        assert_eq!(report.first_invalid().map(|i| i.value), Some(3))
+    }
+
+    #[test]
+    fn test_all_numbers_valid_with_repeated_number_in_preamble() {
+        let sequence = [3, 4, 3, 7, 10];
+        assert!(all_numbers_valid(&sequence, 3).is_valid());
+        let sequence = [5, 5, 10];
+        let report = all_numbers_valid(&sequence, 2);
+        assert_eq!(report.first_invalid().map(|i| i.value), Some(10))
     }
 
     #[test]
@@ -128,10 +527,52 @@ mod test {
             35, 20, 15, 25, 47, 40, 62, 55, 65, 95, 102, 117, 150, 182, 127, 219, 299, 277, 309,
             576,
         ];
-        let correct_sequence = vec![15, 25, 47, 40];
         assert_eq!(
-            find_sequence_that_sums_target(&sequence, 127),
-            Some(correct_sequence)
+            find_sequence_that_sums_target(&sequence, &127, MIN_WEAKNESS_LENGTH),
+            Ok(2..6)
+        );
+        assert_eq!(sequence[2..6], [15, 25, 47, 40])
+    }
+
+    #[test]
+    fn test_find_sequence_that_sums_target_two_numbers() {
+        let sequence = [1, 2, 40, 0, 9];
+        assert_eq!(find_sequence_that_sums_target(&sequence, &42, 2), Ok(1..3));
+        assert_eq!(find_sequence_that_sums_target(&sequence, &42, 3), Ok(1..4));
+        assert_eq!(
+            find_sequence_that_sums_target(&sequence, &42, 4),
+            Err(WeaknessError::NoRange)
+        )
+    }
+
+    #[test]
+    fn test_find_sequence_that_sums_target_min_length() {
+        let sequence = [5, 0, 0, 3, 2];
+        assert_eq!(find_sequence_that_sums_target(&sequence, &5, 1), Ok(0..1));
+        assert_eq!(find_sequence_that_sums_target(&sequence, &5, 3), Ok(0..3));
+        assert_eq!(
+            find_sequence_that_sums_target(&sequence, &5, 5),
+            Err(WeaknessError::NoRange)
+        )
+    }
+
+    #[test]
+    fn test_find_sequence_that_sums_target_with_negative_numbers() {
+        let sequence = [4, -3, 10, -2, 7];
+        assert_eq!(find_sequence_that_sums_target(&sequence, &15, 2), Ok(2..5));
+        assert_eq!(find_sequence_that_sums_target(&sequence, &11, 2), Ok(0..3));
+        assert_eq!(
+            find_sequence_that_sums_target(&sequence, &100, 2),
+            Err(WeaknessError::NoRange)
+        )
+    }
+
+    #[test]
+    fn test_find_sequence_that_sums_target_long_input() {
+        let sequence = vec![1; 1_000_000];
+        assert_eq!(
+            find_sequence_that_sums_target(&sequence, &500_000, 2),
+            Ok(0..500_000)
         )
     }
 
@@ -141,6 +582,136 @@ mod test {
             35, 20, 15, 25, 47, 40, 62, 55, 65, 95, 102, 117, 150, 182, 127, 219, 299, 277, 309,
             576,
         ];
-        assert_eq!(encryption_weakness(&sequence, 127), Some(62))
+        assert_eq!(encryption_weakness(&sequence, &127), Ok(62))
+    }
+
+    #[test]
+    fn test_invalid_numbers() {
+        let sequence = [
+            35, 20, 15, 25, 47, 40, 62, 55, 65, 95, 102, 117, 150, 182, 127, 219, 299, 277, 309,
+            576,
+        ];
+        let invalid = invalid_numbers(sequence.iter().copied(), 5).collect::<Vec<_>>();
+        assert_eq!(invalid, all_numbers_valid(&sequence, 5).invalid)
+    }
+
+    #[test]
+    fn test_invalid_numbers_is_lazy() {
+        let invalid = invalid_numbers(1.., 2)
+            .take(3)
+            .map(|i| (i.index, i.value))
+            .collect::<Vec<_>>();
+        assert_eq!(invalid, vec![(3, 4), (4, 5), (5, 6)])
+    }
+
+    #[test]
+    fn test_invalid_numbers_with_empty_preamble() {
+        let invalid = invalid_numbers(vec![1, 2], 0)
+            .map(|i| (i.index, i.value, i.preamble))
+            .collect::<Vec<_>>();
+        assert_eq!(invalid, vec![(0, 1, vec![]), (1, 2, vec![])])
+    }
+
+    #[test]
+    fn test_all_numbers_valid_with_empty_preamble() {
+        let sequence = [1, 2, 3];
+        let indices = |invalid: Vec<InvalidNumber<i32>>| {
+            invalid.into_iter().map(|i| i.index).collect::<Vec<usize>>()
+        };
+        assert_eq!(
+            indices(all_numbers_valid(&sequence, 0).invalid),
+            indices(invalid_numbers(sequence, 0).collect())
+        );
+        assert_eq!(indices(all_numbers_valid(&sequence, 0).invalid), [0, 1, 2]);
+    }
+
+    #[test]
+    fn test_invalid_numbers_in_reader() {
+        let input = io::Cursor::new("1\n2\n3\n5\n8\n3\n\n20\n");
+        let invalid = invalid_numbers_in_reader(input, 2)
+            .map(|i| i.map(|i| (i.index, i.value, i.preamble)))
+            .collect::<io::Result<Vec<_>>>()
+            .expect("Unable to read input");
+        assert_eq!(invalid, vec![(5, 3, vec![5, 8]), (6, 20, vec![8, 3])])
+    }
+
+    #[test]
+    fn test_invalid_numbers_in_reader_parse_error() {
+        let input = io::Cursor::new("1\n2\nthree\n");
+        let results = invalid_numbers_in_reader::<i64, _>(input, 2).collect::<Vec<_>>();
+        assert_eq!(results.len(), 1);
+        assert_eq!(
+            results[0].as_ref().unwrap_err().kind(),
+            io::ErrorKind::InvalidData
+        )
+    }
+
+    #[test]
+    fn test_all_numbers_valid_i128() {
+        let big = i64::MAX as i128;
+        let sequence = [big, big + 1, 2 * big + 1, 4 * big];
+        let report = all_numbers_valid(&sequence, 2);
+        assert_eq!(report.first_invalid().map(|i| i.value), Some(4 * big))
+    }
+
+    #[test]
+    fn test_all_numbers_valid_near_i64_max() {
+        let sequence = [i64::MAX - 1, 1, i64::MAX, i64::MAX];
+        let report = all_numbers_valid(&sequence, 2);
+        assert_eq!(
+            report.first_invalid().map(|i| (i.index, i.closest_above)),
+            Some((3, None))
+        )
+    }
+
+    #[test]
+    fn test_all_numbers_valid_big_int() {
+        let input = "100000000000000000000000000000\n\
+                     200000000000000000000000000000\n\
+                     300000000000000000000000000000\n\
+                     600000000000000000000000000000\n";
+        let invalid = invalid_numbers_in_reader::<BigInt, _>(io::Cursor::new(input), 2)
+            .map(|i| i.map(|i| i.value.to_string()))
+            .collect::<io::Result<Vec<_>>>()
+            .expect("Unable to read input");
+        assert_eq!(invalid, vec!["600000000000000000000000000000".to_string()])
+    }
+
+    #[test]
+    fn test_find_sequence_after_prefix_overflow() {
+        // Both 1..5 and 3..5 sum to 7. The earliest start is returned.
+        assert_eq!(
+            find_sequence_that_sums_target(&[i64::MAX, 1, -1, 3, 4], &7, 2),
+            Ok(1..5)
+        );
+        assert_eq!(
+            find_sequence_that_sums_target(&[i64::MIN, -1, 2, 6, -2], &4, 2),
+            Ok(3..5)
+        );
+        assert_eq!(u128::MAX.to_big_int().to_string(), u128::MAX.to_string());
+    }
+
+    #[test]
+    fn test_encryption_weakness_overflow() {
+        // The prefix sums overflow, but no run sums to 5 either way.
+        let sequence = [5, -1, i64::MAX, 2];
+        assert_eq!(
+            encryption_weakness(&sequence, &5),
+            Err(WeaknessError::NoRange)
+        );
+        let target = i64::MAX as i128 + 1;
+        let sequence = sequence.iter().map(|n| *n as i128).collect::<Vec<_>>();
+        assert_eq!(
+            encryption_weakness(&sequence, &target),
+            Ok(i64::MAX as i128 - 1)
+        );
+        let sequence = sequence
+            .iter()
+            .map(|n| BigInt::from(*n))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            encryption_weakness(&sequence, &BigInt::from(target)),
+            Ok(BigInt::from(i64::MAX - 1))
+        )
     }
 }
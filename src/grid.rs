@@ -0,0 +1,451 @@
+use std::{
+    error::Error,
+    fmt,
+    ops::{Index, IndexMut},
+};
+
+// What a lookup does with a coordinate that falls off one side of the grid.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum EdgePolicy {
+    // Wraps around to the other side, as if the grid repeats forever.
+    Wrap,
+    // Sticks to the nearest cell on the edge.
+    Clamp,
+    // There is nothing beyond the edge.
+    Bounded,
+}
+
+// Edge policies for each axis, so a map can repeat sideways but still have a
+// bottom. A single EdgePolicy converts into the same policy on both axes.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct Edges {
+    pub horizontal: EdgePolicy,
+    pub vertical: EdgePolicy,
+}
+
+impl From<EdgePolicy> for Edges {
+    fn from(policy: EdgePolicy) -> Self {
+        Edges {
+            horizontal: policy,
+            vertical: policy,
+        }
+    }
+}
+
+impl EdgePolicy {
+    // Maps a coordinate onto an axis of the given length, or None when it is
+    // off a bounded edge or the axis is empty.
+    pub fn resolve(self, position: isize, length: usize) -> Option<usize> {
+        if length == 0 {
+            return None;
+        }
+        match self {
+            EdgePolicy::Wrap => Some(position.rem_euclid(length as isize) as usize),
+            EdgePolicy::Clamp => Some(position.clamp(0, length as isize - 1) as usize),
+            EdgePolicy::Bounded if position >= 0 && (position as usize) < length => {
+                Some(position as usize)
+            }
+            EdgePolicy::Bounded => None,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Neighbourhood {
+    // The four cells sharing a side.
+    Orthogonal,
+    // The eight cells sharing a side or a corner.
+    All,
+}
+
+const ORTHOGONAL: [(isize, isize); 4] = [(0, -1), (-1, 0), (1, 0), (0, 1)];
+const ALL: [(isize, isize); 8] = [
+    (-1, -1),
+    (0, -1),
+    (1, -1),
+    (-1, 0),
+    (1, 0),
+    (-1, 1),
+    (0, 1),
+    (1, 1),
+];
+
+impl Neighbourhood {
+    fn offsets(self) -> &'static [(isize, isize)] {
+        match self {
+            Neighbourhood::Orthogonal => &ORTHOGONAL,
+            Neighbourhood::All => &ALL,
+        }
+    }
+}
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum GridError {
+    // A row had a different width from the first row. Rows count from 1.
+    RaggedRow {
+        row: usize,
+        expected: usize,
+        found: usize,
+    },
+    // A character the cell parser did not recognise. Rows and columns count
+    // from 1.
+    UnexpectedCharacter {
+        row: usize,
+        column: usize,
+        found: char,
+    },
+}
+
+impl fmt::Display for GridError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            GridError::RaggedRow {
+                row,
+                expected,
+                found,
+            } => write!(
+                f,
+                "row {} is {} cells wide, expected {}",
+                row, found, expected
+            ),
+            GridError::UnexpectedCharacter { row, column, found } => write!(
+                f,
+                "row {}, column {}: unexpected character {:?}",
+                row, column, found
+            ),
+        }
+    }
+}
+
+impl Error for GridError {}
+
+// A rectangular grid stored row by row. Positions are (x, y) with x counting
+// columns from the left and y counting rows from the top, both from 0.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Grid<T> {
+    width: usize,
+    height: usize,
+    cells: Vec<T>,
+}
+
+impl<T> Grid<T> {
+    pub fn new(width: usize, height: usize, fill: T) -> Grid<T>
+    where
+        T: Clone,
+    {
+        Grid {
+            width,
+            height,
+            cells: vec![fill; width * height],
+        }
+    }
+
+    pub fn from_rows<I>(rows: I) -> Result<Grid<T>, GridError>
+    where
+        I: IntoIterator<Item = Vec<T>>,
+    {
+        let mut grid = Grid {
+            width: 0,
+            height: 0,
+            cells: Vec::new(),
+        };
+        for row in rows {
+            if grid.height == 0 {
+                grid.width = row.len();
+            } else if row.len() != grid.width {
+                return Err(GridError::RaggedRow {
+                    row: grid.height + 1,
+                    expected: grid.width,
+                    found: row.len(),
+                });
+            }
+            grid.cells.extend(row);
+            grid.height += 1;
+        }
+        Ok(grid)
+    }
+
+    // Builds a grid from lines of text, one cell per character. The parser
+    // returns None for characters it does not recognise.
+    pub fn from_chars<S, F>(lines: &[S], mut parse: F) -> Result<Grid<T>, GridError>
+    where
+        S: AsRef<str>,
+        F: FnMut(char) -> Option<T>,
+    {
+        let mut rows = Vec::with_capacity(lines.len());
+        for (y, line) in lines.iter().enumerate() {
+            let row = line
+                .as_ref()
+                .chars()
+                .enumerate()
+                .map(|(x, c)| {
+                    parse(c).ok_or(GridError::UnexpectedCharacter {
+                        row: y + 1,
+                        column: x + 1,
+                        found: c,
+                    })
+                })
+                .collect::<Result<Vec<T>, GridError>>()?;
+            rows.push(row);
+        }
+        Grid::from_rows(rows)
+    }
+
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    pub fn height(&self) -> usize {
+        self.height
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.cells.is_empty()
+    }
+
+    pub fn get(&self, x: usize, y: usize) -> Option<&T> {
+        if x < self.width && y < self.height {
+            self.cells.get(y * self.width + x)
+        } else {
+            None
+        }
+    }
+
+    pub fn get_mut(&mut self, x: usize, y: usize) -> Option<&mut T> {
+        if x < self.width && y < self.height {
+            self.cells.get_mut(y * self.width + x)
+        } else {
+            None
+        }
+    }
+
+    // Maps a position that may lie outside the grid onto a cell, following
+    // the edge policy for each axis.
+    pub fn resolve(&self, x: isize, y: isize, edges: impl Into<Edges>) -> Option<(usize, usize)> {
+        let edges = edges.into();
+        Some((
+            edges.horizontal.resolve(x, self.width)?,
+            edges.vertical.resolve(y, self.height)?,
+        ))
+    }
+
+    pub fn get_at(&self, x: isize, y: isize, edges: impl Into<Edges>) -> Option<&T> {
+        let (x, y) = self.resolve(x, y, edges)?;
+        self.get(x, y)
+    }
+
+    pub fn row(&self, y: usize) -> Option<&[T]> {
+        if y < self.height {
+            Some(&self.cells[y * self.width..(y + 1) * self.width])
+        } else {
+            None
+        }
+    }
+
+    pub fn rows(&self) -> impl Iterator<Item = &[T]> {
+        (0..self.height).map(move |y| &self.cells[y * self.width..(y + 1) * self.width])
+    }
+
+    pub fn column(&self, x: usize) -> Option<impl Iterator<Item = &T>> {
+        if x < self.width {
+            Some(self.cells.iter().skip(x).step_by(self.width))
+        } else {
+            None
+        }
+    }
+
+    // Every position in reading order, paired with its cell.
+    pub fn iter(&self) -> impl Iterator<Item = ((usize, usize), &T)> {
+        let width = self.width;
+        self.cells
+            .iter()
+            .enumerate()
+            .map(move |(idx, cell)| ((idx % width, idx / width), cell))
+    }
+
+    // The cells around a position, each given once. Neighbours that resolve
+    // back onto the position itself, as happens when clamping at an edge, are
+    // left out, as are repeats, as happen when clamping or wrapping a narrow
+    // grid.
+    pub fn neighbours(
+        &self,
+        x: usize,
+        y: usize,
+        neighbourhood: Neighbourhood,
+        edges: impl Into<Edges>,
+    ) -> impl Iterator<Item = ((usize, usize), &T)> {
+        let edges = edges.into();
+        let mut seen = Vec::with_capacity(8);
+        neighbourhood
+            .offsets()
+            .iter()
+            .filter_map(move |(dx, dy)| self.resolve(x as isize + dx, y as isize + dy, edges))
+            .filter(move |&position| {
+                if position == (x, y) || seen.contains(&position) {
+                    return false;
+                }
+                seen.push(position);
+                true
+            })
+            .map(move |(nx, ny)| ((nx, ny), &self[(nx, ny)]))
+    }
+
+    pub fn map<U, F>(&self, f: F) -> Grid<U>
+    where
+        F: FnMut(&T) -> U,
+    {
+        Grid {
+            width: self.width,
+            height: self.height,
+            cells: self.cells.iter().map(f).collect(),
+        }
+    }
+}
+
+impl<T> Index<(usize, usize)> for Grid<T> {
+    type Output = T;
+
+    fn index(&self, (x, y): (usize, usize)) -> &T {
+        self.get(x, y).expect("Position should be inside the grid")
+    }
+}
+
+impl<T> IndexMut<(usize, usize)> for Grid<T> {
+    fn index_mut(&mut self, (x, y): (usize, usize)) -> &mut T {
+        self.get_mut(x, y)
+            .expect("Position should be inside the grid")
+    }
+}
+
+// Writes one line per row, so cells that display as a single character print
+// the grid back in the form it was parsed from.
+impl<T: fmt::Display> fmt::Display for Grid<T> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for (y, row) in self.rows().enumerate() {
+            if y > 0 {
+                writeln!(f)?;
+            }
+            for cell in row {
+                write!(f, "{}", cell)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn example() -> Grid<char> {
+        Grid::from_chars(&["abc", "def", "ghi"], Some).expect("Grid should parse")
+    }
+
+    #[test]
+    fn test_from_chars_and_display() {
+        let grid = example();
+        assert_eq!((grid.width(), grid.height()), (3, 3));
+        assert_eq!(grid[(1, 2)], 'h');
+        assert_eq!(grid.to_string(), "abc\ndef\nghi");
+        assert_eq!(
+            Grid::from_chars(&["ab", "c"], Some),
+            Err(GridError::RaggedRow {
+                row: 2,
+                expected: 2,
+                found: 1
+            })
+        );
+        assert_eq!(
+            Grid::from_chars(&["..", ".x"], |c| if c == '.' { Some(()) } else { None }),
+            Err(GridError::UnexpectedCharacter {
+                row: 2,
+                column: 2,
+                found: 'x'
+            })
+        );
+    }
+
+    #[test]
+    fn test_rows_and_columns() {
+        let grid = example();
+        assert_eq!(grid.row(1), Some(&['d', 'e', 'f'][..]));
+        assert_eq!(grid.row(3), None);
+        assert_eq!(grid.rows().count(), 3);
+        let column = grid
+            .column(2)
+            .expect("Column should exist")
+            .collect::<String>();
+        assert_eq!(column, "cfi");
+        assert!(grid.column(3).is_none());
+    }
+
+    #[test]
+    fn test_edge_policies() {
+        let grid = example();
+        assert_eq!(grid.get_at(-1, 4, EdgePolicy::Wrap), Some(&'f'));
+        assert_eq!(grid.get_at(-1, 4, EdgePolicy::Clamp), Some(&'g'));
+        assert_eq!(grid.get_at(-1, 1, EdgePolicy::Bounded), None);
+        let edges = Edges {
+            horizontal: EdgePolicy::Wrap,
+            vertical: EdgePolicy::Bounded,
+        };
+        assert_eq!(grid.get_at(7, 2, edges), Some(&'h'));
+        assert_eq!(grid.get_at(7, 3, edges), None);
+        let empty = Grid::<char>::from_rows(Vec::new()).expect("Empty grid is rectangular");
+        assert!(empty.is_empty());
+        assert_eq!(empty.get_at(0, 0, EdgePolicy::Wrap), None);
+    }
+
+    #[test]
+    fn test_neighbours() {
+        let grid = example();
+        let around = |x, y, neighbourhood, edges: EdgePolicy| {
+            grid.neighbours(x, y, neighbourhood, edges)
+                .map(|(_, c)| *c)
+                .collect::<String>()
+        };
+        assert_eq!(
+            around(1, 1, Neighbourhood::Orthogonal, EdgePolicy::Bounded),
+            "bdfh"
+        );
+        assert_eq!(
+            around(1, 1, Neighbourhood::All, EdgePolicy::Bounded),
+            "abcdfghi"
+        );
+        assert_eq!(around(0, 0, Neighbourhood::All, EdgePolicy::Bounded), "bde");
+        assert_eq!(
+            around(0, 0, Neighbourhood::Orthogonal, EdgePolicy::Wrap),
+            "gcbd"
+        );
+        assert_eq!(
+            around(0, 0, Neighbourhood::Orthogonal, EdgePolicy::Clamp),
+            "bd"
+        );
+        assert_eq!(around(0, 0, Neighbourhood::All, EdgePolicy::Clamp), "bde");
+        assert_eq!(around(2, 2, Neighbourhood::All, EdgePolicy::Clamp), "efh");
+        let narrow = Grid::from_rows(Vec::from([Vec::from(['a', 'b'])])).expect("Rows are even");
+        let positions = narrow
+            .neighbours(0, 0, Neighbourhood::Orthogonal, EdgePolicy::Wrap)
+            .map(|(p, _)| p)
+            .collect::<Vec<_>>();
+        assert_eq!(positions, [(1, 0)]);
+        assert_eq!(
+            narrow
+                .neighbours(1, 0, Neighbourhood::All, EdgePolicy::Wrap)
+                .count(),
+            1
+        );
+    }
+
+    #[test]
+    fn test_map_and_mutation() {
+        let mut grid = example().map(|c| "aeiou".contains(*c));
+        grid[(2, 0)] = true;
+        let marked = grid
+            .iter()
+            .filter(|(_, v)| **v)
+            .map(|(p, _)| p)
+            .collect::<Vec<_>>();
+        assert_eq!(marked, [(0, 0), (2, 0), (1, 1), (2, 2)]);
+    }
+}
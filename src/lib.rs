@@ -0,0 +1,13 @@
+pub mod answer;
+pub mod big_int;
+pub mod day_1;
+pub mod day_2;
+pub mod day_3;
+pub mod day_4;
+#[allow(dead_code)]
+mod day_5;
+#[allow(dead_code)]
+mod day_8;
+pub mod day_9;
+pub mod grid;
+pub mod random;
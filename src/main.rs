@@ -1,60 +1,146 @@
-mod day_1;
-mod day_2;
-mod day_3;
-mod day_4;
-mod day_5;
-mod day_8;
-mod day_9;
+use advent_of_code_2020::{
+    answer::{Answer, OverflowError, Precision},
+    day_1, day_2, day_3, day_4, day_9,
+};
 
 use day_1::{find_three_items_that_sum_2020, find_two_items_that_sum_2020};
-use day_2::{number_of_valid_passwords, PolicyStrategy};
-use day_3::{count_trees, tree_product};
-use day_4::{count_valid_passports, CountType};
-use day_9::{all_numbers_valid, encryption_weakness};
+use day_2::{number_of_valid_passwords, ErrorMode, PasswordCount, PolicyStrategy};
+use day_3::Forest;
+use day_4::{
+    count_valid_passports_with_schema, export_records, redact_records, write_records, CountType,
+    Schema,
+};
+use day_9::{all_numbers_valid, encryption_weakness, WeaknessError};
+use std::{env, io, process};
+
+// How wide products in the answers may grow before they are reported as
+// overflowing.
+const ANSWER_PRECISION: Precision = Precision::Bits64;
+
+fn print_answer(answer: Result<Answer, OverflowError>) {
+    match answer {
+        Ok(answer) => println!("Answer is {}", answer),
+        Err(e) => eprintln!("Unable to compute the answer: {}", e),
+    }
+}
+
+// Usage: export-passports [jsonl|csv] [all|valid|invalid] [file]
+// Writes the Day 4 passports to standard output, checked against the puzzle
+// rules. Defaults to JSON Lines for every passport in day_4.txt.
+fn export_passports(args: &[String]) -> Result<(), String> {
+    let format = args.first().map_or("jsonl", |s| s.as_str()).parse()?;
+    let selection = args.get(1).map_or("all", |s| s.as_str()).parse()?;
+    let file_name = args.get(2).map_or("day_4.txt", |s| s.as_str());
+    let parsed = day_4::read_records(file_name).map_err(|e| format!("{}: {}", file_name, e))?;
+    for warning in &parsed.warnings {
+        eprintln!("Skipped {}", warning);
+    }
+    export_records(
+        io::stdout().lock(),
+        &parsed.records,
+        &Schema::puzzle(),
+        CountType::KeysAndValues,
+        format,
+        selection,
+    )
+    .map_err(|e| format!("unable to export passports: {}", e))?;
+    Ok(())
+}
+
+// Usage: redact-passports [seed] [file]
+// Writes the Day 4 passports to standard output with personal values swapped
+// for made up ones that are valid exactly when the originals were.
+fn redact_passports(args: &[String]) -> Result<(), String> {
+    let seed = match args.first() {
+        Some(seed) => seed
+            .parse()
+            .map_err(|_| format!("invalid seed {:?}", seed))?,
+        None => 2020,
+    };
+    let file_name = args.get(1).map_or("day_4.txt", |s| s.as_str());
+    let parsed = day_4::read_records(file_name).map_err(|e| format!("{}: {}", file_name, e))?;
+    write_records(io::stdout().lock(), &redact_records(&parsed.records, seed))
+        .map_err(|e| format!("unable to write passports: {}", e))
+}
 
 fn main() {
+    let args = env::args().skip(1).collect::<Vec<String>>();
+    let command = match args.first().map(|s| s.as_str()) {
+        Some("export-passports") => Some(export_passports(&args[1..])),
+        Some("redact-passports") => Some(redact_passports(&args[1..])),
+        _ => None,
+    };
+    if let Some(result) = command {
+        if let Err(e) = result {
+            eprintln!("{}", e);
+            process::exit(1);
+        }
+        return;
+    }
+
     println!("Day 1 part 1");
     let day_1_input = day_1::load_input_file("day_1.txt").expect("Missing input file");
     if let Some(numbers) = find_two_items_that_sum_2020(&day_1_input) {
-        let answer = numbers.0 * numbers.1;
-        println!("Answer is {}", answer)
+        print_answer(Answer::product([numbers.0, numbers.1], ANSWER_PRECISION));
     };
     println!("Day 1 part 2");
     if let Some(numbers) = find_three_items_that_sum_2020(&day_1_input) {
-        let answer = numbers[0] * numbers[1] * numbers[2];
-        println!("Answer is {}", answer)
+        print_answer(Answer::product(numbers, ANSWER_PRECISION));
     };
 
     println!("Day 2 part 1");
     let day_2_input = day_2::load_input_file("day_2.txt").expect("Missing input file");
-    let answer = number_of_valid_passwords(&day_2_input, PolicyStrategy::MinMax);
-    println!("Answer is {}", answer);
+    let answer = number_of_valid_passwords(&day_2_input, &PolicyStrategy::MinMax, ErrorMode::Skip)
+        .expect("Skipping never aborts");
+    print_password_count(&answer);
     println!("Day 2 part 2");
-    let answer = number_of_valid_passwords(&day_2_input, PolicyStrategy::Position);
-    println!("Answer is {}", answer);
+    let answer =
+        number_of_valid_passwords(&day_2_input, &PolicyStrategy::Position, ErrorMode::Skip)
+            .expect("Skipping never aborts");
+    print_password_count(&answer);
 
     println!("Day 3 part 1");
     let day_3_input = day_3::load_input_file("day_3.txt").expect("Missing input file");
-    let answer = count_trees(&day_3_input, (3, 1));
+    let forest = Forest::parse(&day_3_input).expect("Invalid map");
+    let answer = forest.count_trees((3, 1));
     println!("Answer is {}", answer);
     println!("Day 3 part 2");
     let slopes = Vec::from([(1, 1), (3, 1), (5, 1), (7, 1), (1, 2)]);
-    let answer = tree_product(&day_3_input, &slopes);
-    println!("Answer is {}", answer);
+    print_answer(forest.tree_product(&slopes, ANSWER_PRECISION));
 
     println!("Day 4 part 1");
     let day_4_input = day_4::load_input_file("day_4.txt").expect("Missing input file");
-    let answer = count_valid_passports(&day_4_input, CountType::KeysOnly);
+    let schema = Schema::puzzle();
+    let answer = count_valid_passports_with_schema(&day_4_input, &schema, CountType::KeysOnly);
     println!("Answer is {}", answer);
     println!("Day 4 part 2");
-    let answer = count_valid_passports(&day_4_input, CountType::KeysAndValues);
+    let answer = count_valid_passports_with_schema(&day_4_input, &schema, CountType::KeysAndValues);
     println!("Answer is {}", answer);
 
     println!("Day 9 part 1");
-    let day_9_input = day_9::load_input_file("day_9.txt").expect("Missing input file");
-    let answer_1 = all_numbers_valid(&day_9_input, 25);
-    println!("Answer is {}", answer_1.1.unwrap());
-    println!("Day 9 part 2");
-    let answer_2 = encryption_weakness(&day_9_input, answer_1.1.unwrap());
-    println!("Answer is {}", answer_2.unwrap());
+    let day_9_input = day_9::load_input_file::<i64>("day_9.txt").expect("Missing input file");
+    let report = all_numbers_valid(&day_9_input, 25);
+    match report.first_invalid() {
+        Some(invalid) => {
+            println!("Answer is {}", invalid.value);
+            println!("Day 9 part 2");
+            match encryption_weakness(&day_9_input, &invalid.value) {
+                Ok(answer) => println!("Answer is {}", answer),
+                Err(WeaknessError::NoRange) => println!("No encryption weakness found"),
+                Err(WeaknessError::Overflow) => println!("Encryption weakness overflowed"),
+            }
+        }
+        None => {
+            println!("All numbers are valid");
+            println!("Day 9 part 2");
+            println!("No invalid number to find a weakness for");
+        }
+    }
+}
+
+fn print_password_count(count: &PasswordCount) {
+    for line_error in &count.skipped {
+        eprintln!("Skipped {}", line_error)
+    }
+    println!("Answer is {}", count.valid);
 }
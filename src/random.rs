@@ -0,0 +1,63 @@
+// A small seeded generator, a 64-bit linear congruential generator. It is
+// only meant for repeatable made up data: the same seed always gives the same
+// numbers.
+#[derive(Clone, Debug)]
+pub struct Lcg {
+    state: u64,
+}
+
+impl Lcg {
+    pub fn new(seed: u64) -> Lcg {
+        Lcg { state: seed }
+    }
+
+    // The next number, from the high half of the state, as the low bits of
+    // an LCG repeat after only a few steps.
+    pub fn next_u32(&mut self) -> u32 {
+        self.state = self
+            .state
+            .wrapping_mul(6364136223846793005)
+            .wrapping_add(1442695040888963407);
+        (self.state >> 32) as u32
+    }
+
+    // A number from 0 up to but not including the bound.
+    pub fn below(&mut self, bound: u64) -> u64 {
+        u64::from(self.next_u32()) % bound
+    }
+
+    pub fn pick(&mut self, choices: &str) -> char {
+        let choices = choices.chars().collect::<Vec<char>>();
+        choices[self.below(choices.len() as u64) as usize]
+    }
+
+    pub fn string(&mut self, length: usize, choices: &str) -> String {
+        (0..length).map(|_| self.pick(choices)).collect()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_same_seed_same_numbers() {
+        let mut a = Lcg::new(2020);
+        let mut b = Lcg::new(2020);
+        let numbers = (0..5).map(|_| a.next_u32()).collect::<Vec<u32>>();
+        assert_eq!(numbers, (0..5).map(|_| b.next_u32()).collect::<Vec<u32>>());
+        assert_ne!(Lcg::new(2021).next_u32(), numbers[0]);
+    }
+
+    #[test]
+    fn test_below_and_pick_stay_in_range() {
+        let mut rng = Lcg::new(7);
+        for _ in 0..1000 {
+            assert!(rng.below(3) < 3);
+            assert!("ab\u{e9}".contains(rng.pick("ab\u{e9}")));
+        }
+        let value = rng.string(12, "01");
+        assert_eq!(value.len(), 12);
+        assert!(value.bytes().all(|b| b == b'0' || b == b'1'));
+    }
+}